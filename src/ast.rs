@@ -1,12 +1,13 @@
+use crate::MnemonicWidth;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::convert::{From, TryFrom};
 use std::fmt;
 use std::str::FromStr;
-use strum_macros::{Display, EnumString};
+use strum_macros::{Display, EnumIter, EnumString};
 use thiserror::Error;
 
-static REG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"r\d{1,2}").unwrap());
+static REG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^r\d{1,2}$").unwrap());
 static SPACE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
 
 #[derive(Error, Debug)]
@@ -43,6 +44,8 @@ pub enum Immediate {
     Long(u64),
     LocalLabel(String),
     Label(String),
+    Hi(String),
+    Lo(String),
 }
 
 impl Immediate {
@@ -65,7 +68,10 @@ impl Immediate {
     }
 
     pub fn is_label(&self) -> bool {
-        matches!(self, Immediate::Label(_) | Immediate::LocalLabel(_))
+        matches!(
+            self,
+            Immediate::Label(_) | Immediate::LocalLabel(_) | Immediate::Hi(_) | Immediate::Lo(_)
+        )
     }
 
     pub fn new<T>(val: T) -> Self
@@ -141,6 +147,11 @@ pub enum Instruction {
     Bytes {
         bytes: u32,
     },
+    /// Padding from `.align`, `.org` and `.space`: `count` copies of the word `bytes`.
+    Fill {
+        bytes: u32,
+        count: u32,
+    },
 }
 
 type I = ITypeOp;
@@ -148,41 +159,9 @@ type R = RTypeOp;
 type J = JTypeOp;
 
 impl Instruction {
+    /// Whether the instruction is a branch or jump, and so is followed by a delay slot.
     pub fn has_delay_slot(&self) -> bool {
-        match self {
-            Instruction::Jump { .. } => true,
-            Instruction::Immediate { op, .. } => matches!(
-                op,
-                I::Beqz
-                    | I::Bgtz
-                    | I::Bgtzl
-                    | I::Blez
-                    | I::Blezl
-                    | I::Bnez
-                    | I::Beq
-                    | I::Beql
-                    | I::Bne
-                    | I::Bnel
-                    | I::Bgez
-                    | I::Bgezal
-                    | I::Bgezall
-                    | I::Bgezl
-                    | I::Bltz
-                    | I::Bltzal
-                    | I::Bltzall
-                    | I::Bltzl
-                    | I::Bc0f
-                    | I::Bc1f
-                    | I::Bc0fl
-                    | I::Bc1fl
-                    | I::Bc0t
-                    | I::Bc1t
-                    | I::Bc0tl
-                    | I::Bc1tl
-            ),
-            Instruction::Register { op, .. } => matches!(op, R::Jr | R::Jalr),
-            Instruction::Bytes { .. } => false,
-        }
+        self.is_branch() || self.is_jump()
     }
 
     pub fn push_bytes(&mut self, bytes: &mut Vec<u32>) {
@@ -200,50 +179,148 @@ impl Instruction {
             | Instruction::Jump { bytes: b, .. }
             | Instruction::Register { bytes: b, .. } => b.clone(),
             Instruction::Bytes { bytes: b } => vec![*b],
+            Instruction::Fill { bytes: b, count } => vec![*b; *count as usize],
         }
     }
 
+    /// Whether the instruction is a branch to an offset from its own address, including the branch
+    /// pseudo-instructions such as `b` and `blt`.
     pub fn is_branch(&self) -> bool {
         match &self {
             Instruction::Immediate { op, .. } => matches!(
                 op,
-                I::Bltz
-                    | I::Bgez
-                    | I::Bltzl
-                    | I::Bgezl
-                    | I::Bltzal
-                    | I::Bgezal
-                    | I::Bltzall
-                    | I::Bgezall
-                    | I::Bal
+                I::B | I::Bal
                     | I::Bc0f
-                    | I::Bc0t
                     | I::Bc0fl
+                    | I::Bc0t
                     | I::Bc0tl
                     | I::Bc1f
-                    | I::Bc1t
                     | I::Bc1fl
+                    | I::Bc1t
                     | I::Bc1tl
                     | I::Beq
-                    | I::Bne
                     | I::Beql
-                    | I::Bnel
-                    | I::Blez
-                    | I::Blezl
+                    | I::Beqz
+                    | I::Beqzl
+                    | I::Bge
+                    | I::Bgel
+                    | I::Bgeu
+                    | I::Bgeul
+                    | I::Bgez
+                    | I::Bgezal
+                    | I::Bgezall
+                    | I::Bgezl
+                    | I::Bgt
+                    | I::Bgtl
+                    | I::Bgtu
+                    | I::Bgtul
                     | I::Bgtz
                     | I::Bgtzl
+                    | I::Ble
+                    | I::Blel
+                    | I::Bleu
+                    | I::Bleul
+                    | I::Blez
+                    | I::Blezl
+                    | I::Blt
+                    | I::Bltl
+                    | I::Bltu
+                    | I::Bltul
+                    | I::Bltz
+                    | I::Bltzal
+                    | I::Bltzall
+                    | I::Bltzl
+                    | I::Bne
+                    | I::Bnel
+                    | I::Bnez
+                    | I::Bnezl
             ),
             _ => false,
         }
     }
 
+    /// Whether the instruction is a jump to an absolute address or to the address in a register.
+    pub fn is_jump(&self) -> bool {
+        matches!(
+            self,
+            Instruction::Jump { .. }
+                | Instruction::Register {
+                    op: R::Jalr | R::JalrHb | R::Jr | R::JrHb,
+                    ..
+                }
+        )
+    }
+
+    /// Whether the instruction is a branch that is not always taken.
+    ///
+    /// Branches that compare `$zero` or a register with itself so that they are always taken, such
+    /// as `b` and `bal`, are unconditional.
+    pub fn is_conditional_branch(&self) -> bool {
+        match self {
+            Instruction::Immediate {
+                op: I::B | I::Bal, ..
+            } => false,
+            Instruction::Immediate {
+                op: I::Beq | I::Beql,
+                rs,
+                rt,
+                ..
+            } => rs != rt,
+            Instruction::Immediate {
+                op: I::Bgez | I::Bgezal | I::Bgezall | I::Bgezl | I::Blez | I::Blezl,
+                rs,
+                ..
+            } => *rs != Register::Zero,
+            _ => self.is_branch(),
+        }
+    }
+
     pub fn is_unconditional_jump(&self) -> bool {
         matches!(
             self,
-            Instruction::Jump { op: J::J, .. } | Instruction::Register { op: R::Jr, .. }
+            Instruction::Jump { op: J::J, .. }
+                | Instruction::Register {
+                    op: R::Jr | R::JrHb,
+                    ..
+                }
         )
     }
 
+    /// The address that a branch or jump at `pc` goes to when it is taken.
+    ///
+    /// Returns `None` for other instructions, jumps to the address in a register, and branches and
+    /// jumps to labels that haven't been resolved yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let insts = Mipsasm::new()
+    ///     .base(0x8000_0000)
+    ///     .assemble("foo:\nnop\nbnez $a0, foo\nnop\njal foo\nnop")
+    ///     .unwrap();
+    /// assert_eq!(insts[1].branch_target(0x8000_0004), Some(0x8000_0000));
+    /// assert_eq!(insts[3].branch_target(0x8000_000c), Some(0x8000_0000));
+    /// assert_eq!(insts[0].branch_target(0x8000_0000), None);
+    /// ```
+    pub fn branch_target(&self, pc: u32) -> Option<u32> {
+        match self {
+            Instruction::Immediate {
+                imm: Immediate::Short(offset),
+                ..
+            } if self.is_branch() => {
+                let offset = *offset as i16 as i32 * 4;
+                Some(pc.wrapping_add(4).wrapping_add(offset as u32))
+            }
+            Instruction::Jump {
+                target: Target::Address(target),
+                ..
+            } => Some(*target),
+            _ => None,
+        }
+    }
+
     pub fn get_branch_offset(&self) -> i32 {
         match &self {
             Instruction::Immediate { imm, .. } => imm.as_u32() as i32,
@@ -258,6 +335,28 @@ impl Instruction {
         }
     }
 
+    // Returns the name of the operation, or `None` for data
+    pub(crate) fn mnemonic(&self) -> Option<String> {
+        match self {
+            Instruction::Immediate { op, .. } => Some(op.to_string()),
+            Instruction::Jump { op, .. } => Some(op.to_string()),
+            Instruction::Register { op, .. } => Some(op.to_string()),
+            Instruction::Bytes { .. } | Instruction::Fill { .. } => None,
+        }
+    }
+
+    // Returns the code field of a syscall, break or register trap instruction
+    pub fn get_code(&self) -> Option<u32> {
+        match self {
+            Instruction::Register {
+                op: R::Break | R::Syscall | R::Teq | R::Tge | R::Tgeu | R::Tlt | R::Tltu | R::Tne,
+                sa,
+                ..
+            } => Some(*sa),
+            _ => None,
+        }
+    }
+
     pub fn to_le_bytes(&self) -> [u8; 4] {
         match self {
             Instruction::Immediate { bytes, .. }
@@ -267,13 +366,33 @@ impl Instruction {
                 b.copy_from_slice(&bytes[0].to_le_bytes());
                 b
             }
-            Instruction::Bytes { bytes } => bytes.to_le_bytes(),
+            Instruction::Bytes { bytes } | Instruction::Fill { bytes, .. } => bytes.to_le_bytes(),
         }
     }
 }
 
-impl fmt::Display for Instruction {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl Instruction {
+    /// Wraps the instruction for formatting with registers named by number if `numeric_regs` is set
+    pub(crate) fn styled(&self, numeric_regs: bool) -> Styled<'_> {
+        Styled(self, numeric_regs)
+    }
+
+    fn fmt_source(&self, f: &mut fmt::Formatter, numeric_regs: bool) -> fmt::Result {
+        let reg = |r: &Register| {
+            if numeric_regs {
+                r.numeric_name()
+            } else {
+                r.to_string()
+            }
+        };
+        let freg = |r: &Register| {
+            let r = FloatRegister::from(*r);
+            if numeric_regs {
+                r.numeric_name()
+            } else {
+                r.to_string()
+            }
+        };
         match &self {
             Instruction::Immediate {
                 op,
@@ -305,28 +424,52 @@ impl fmt::Display for Instruction {
                 | I::Sw
                 | I::Swl
                 | I::Swr => {
-                    write!(f, "{:11}${}, {:#x}(${})", op, rt, Signed(*imm), rs)
+                    write!(
+                        f,
+                        "{:11}${}, {:#x}(${})",
+                        op,
+                        reg(rt),
+                        Signed(*imm),
+                        reg(rs)
+                    )
                 }
-                I::Cache => {
+                I::Synci => write!(f, "{:11}{:#x}(${})", op, Signed(*imm), reg(rs)),
+                I::Cache | I::Pref => {
                     write!(
                         f,
                         "{:11}{:#x}, {:#x}(${})",
                         op,
                         rt.as_num(),
                         Signed(*imm),
-                        rs
+                        reg(rs)
                     )
                 }
                 I::Addi | I::Addiu | I::Daddi | I::Daddiu | I::Slti | I::Sltiu => {
-                    write!(f, "{:11}${}, ${}, {:#x}", op, rt, rs, Signed(*imm))
+                    write!(
+                        f,
+                        "{:11}${}, ${}, {:#x}",
+                        op,
+                        reg(rt),
+                        reg(rs),
+                        Signed(*imm)
+                    )
+                }
+                I::Andi | I::Ori | I::Xori => {
+                    write!(f, "{:11}${}, ${}, {:#x}", op, reg(rt), reg(rs), imm)
                 }
-                I::Andi | I::Ori | I::Xori => write!(f, "{:11}${}, ${}, {:#x}", op, rt, rs, imm),
-                I::Lui => write!(f, "{:11}${}, {:#x}", op, rt, imm),
+                I::Lui => write!(f, "{:11}${}, {:#x}", op, reg(rt), imm),
                 I::Beqz | I::Bgtz | I::Bgtzl | I::Blez | I::Blezl | I::Bnez => {
-                    write!(f, "{:11}${}, {:#x}", op, rs, Signed(*imm))
+                    write!(f, "{:11}${}, {:#x}", op, reg(rs), Signed(*imm))
                 }
                 I::Beq | I::Beql | I::Bne | I::Bnel => {
-                    write!(f, "{:11}${}, ${}, {:#x}", op, rs, rt, Signed(*imm))
+                    write!(
+                        f,
+                        "{:11}${}, ${}, {:#x}",
+                        op,
+                        reg(rs),
+                        reg(rt),
+                        Signed(*imm)
+                    )
                 }
                 I::Bgez
                 | I::Bgezal
@@ -342,7 +485,7 @@ impl fmt::Display for Instruction {
                 | I::Tlti
                 | I::Tltiu
                 | I::Tnei => {
-                    write!(f, "{:11}${}, {:#x}", op, rs, Signed(*imm))
+                    write!(f, "{:11}${}, {:#x}", op, reg(rs), Signed(*imm))
                 }
                 I::Bc0f
                 | I::Bc1f
@@ -359,9 +502,9 @@ impl fmt::Display for Instruction {
                         f,
                         "{:11}${}, {:#x}(${})",
                         op,
-                        FloatRegister::from(*rt),
+                        freg(rt),
                         Signed(*imm),
-                        rs
+                        reg(rs)
                     )
                 }
                 e => panic!("Unhandled immediate instruction: {:?}", e),
@@ -381,10 +524,10 @@ impl fmt::Display for Instruction {
                 ..
             } => match op {
                 I::Beqz | I::Bgtz | I::Bgtzl | I::Blez | I::Blezl | I::Bnez => {
-                    write!(f, "{:11}${}, {}", op, rs, l)
+                    write!(f, "{:11}${}, {}", op, reg(rs), l)
                 }
                 I::Beq | I::Beql | I::Bne | I::Bnel => {
-                    write!(f, "{:11}${}, ${}, {}", op, rs, rt, l)
+                    write!(f, "{:11}${}, ${}, {}", op, reg(rs), reg(rt), l)
                 }
                 I::Bgez
                 | I::Bgezal
@@ -400,7 +543,7 @@ impl fmt::Display for Instruction {
                 | I::Tlti
                 | I::Tltiu
                 | I::Tnei => {
-                    write!(f, "{:11}${}, {}", op, rs, l)
+                    write!(f, "{:11}${}, {}", op, reg(rs), l)
                 }
                 I::Bc0f
                 | I::Bc1f
@@ -412,8 +555,23 @@ impl fmt::Display for Instruction {
                 | I::Bc1tl => {
                     write!(f, "{:11}{}", op, l)
                 }
+                I::Addiupc | I::Lwpc => write!(f, "{:11}${}, {}", op, reg(rs), l),
                 e => panic!("Unhandled immediate instruction: {:?}", e),
             },
+            Instruction::Immediate {
+                op: op @ (I::Addiupc | I::Lwpc),
+                rs,
+                imm: Immediate::Int(imm),
+                ..
+            } => {
+                // The offset is a signed 19-bit count of words
+                let offset = ((*imm << 13) as i32) >> 13;
+                if offset < 0 {
+                    write!(f, "{:11}${}, -{:#x}", op, reg(rs), offset.unsigned_abs())
+                } else {
+                    write!(f, "{:11}${}, {:#x}", op, reg(rs), offset)
+                }
+            }
             Instruction::Jump {
                 op,
                 target: Target::Address(target),
@@ -436,7 +594,14 @@ impl fmt::Display for Instruction {
                 sa,
                 bytes,
             } => match op {
-                R::Sync => write!(f, "{}", op),
+                R::Ehb | R::Pause | R::Ssnop => write!(f, "{}", op),
+                R::Sync | R::Wait => {
+                    if *sa == 0 {
+                        write!(f, "{}", op)
+                    } else {
+                        write!(f, "{:11}{:#x?}", op, sa)
+                    }
+                }
                 R::Add
                 | R::Addu
                 | R::And
@@ -444,6 +609,8 @@ impl fmt::Display for Instruction {
                 | R::Daddu
                 | R::Dsub
                 | R::Dsubu
+                | R::MovN
+                | R::Mul
                 | R::Nor
                 | R::Or
                 | R::Slt
@@ -451,7 +618,7 @@ impl fmt::Display for Instruction {
                 | R::Sub
                 | R::Subu
                 | R::Xor => {
-                    write!(f, "{:11}${}, ${}, ${}", op, rd, rs, rt)
+                    write!(f, "{:11}${}, ${}, ${}", op, reg(rd), reg(rs), reg(rt))
                 }
                 R::Dsll
                 | R::Dsll32
@@ -462,10 +629,13 @@ impl fmt::Display for Instruction {
                 | R::Sll
                 | R::Sra
                 | R::Srl => {
-                    write!(f, "{:11}${}, ${}, {:#x?}", op, rd, rt, sa)
+                    write!(f, "{:11}${}, ${}, {:#x?}", op, reg(rd), reg(rt), sa)
                 }
                 R::Dsllv | R::Dsrav | R::Dsrlv | R::Sllv | R::Srav | R::Srlv => {
-                    write!(f, "{:11}${}, ${}, ${}", op, rd, rt, rs)
+                    write!(f, "{:11}${}, ${}, ${}", op, reg(rd), reg(rt), reg(rs))
+                }
+                R::Cop0 | R::Cop1 | R::Cop2 => {
+                    write!(f, "{:11}{:#x?}", op, sa)
                 }
                 R::Break | R::Syscall => {
                     if *sa == 0 {
@@ -481,31 +651,35 @@ impl fmt::Display for Instruction {
                 | R::Dmult
                 | R::Dmultu
                 | R::Mult
-                | R::Multu
-                | R::Teq
-                | R::Tge
-                | R::Tgeu
-                | R::Tlt
-                | R::Tltu
-                | R::Tne => {
-                    write!(f, "{:11}${}, ${}", op, rs, rt)
+                | R::Multu => {
+                    write!(f, "{:11}${}, ${}", op, reg(rs), reg(rt))
                 }
-                R::Jalr => {
+                R::Teq | R::Tge | R::Tgeu | R::Tlt | R::Tltu | R::Tne => {
+                    if *sa == 0 {
+                        write!(f, "{:11}${}, ${}", op, reg(rs), reg(rt))
+                    } else {
+                        write!(f, "{:11}${}, ${}, {:#x?}", op, reg(rs), reg(rt), sa)
+                    }
+                }
+                R::Jalr | R::JalrHb => {
                     if let &Register::Ra = rd {
-                        write!(f, "{:11}${}", op, rs)
+                        write!(f, "{:11}${}", op, reg(rs))
                     } else {
-                        write!(f, "{:11}${}, ${}", op, rd, rs)
+                        write!(f, "{:11}${}, ${}", op, reg(rd), reg(rs))
                     }
                 }
-                R::Jr | R::Mthi | R::Mtlo => {
-                    write!(f, "{:11}${}", op, rs)
+                R::Jr | R::JrHb | R::Mthi | R::Mtlo => {
+                    write!(f, "{:11}${}", op, reg(rs))
                 }
                 R::Mfhi | R::Mflo => {
-                    write!(f, "{:11}${}", op, rd)
+                    write!(f, "{:11}${}", op, reg(rd))
+                }
+                R::Rdpgpr | R::Wrpgpr => {
+                    write!(f, "{:11}${}, ${}", op, reg(rd), reg(rt))
                 }
                 R::Cfc0 | R::Ctc0 | R::Dmfc0 | R::Dmtc0 | R::Mfc0 | R::Mtc0 => {
                     if let Ok(rd) = Cop0Register::try_from(*rd) {
-                        write!(f, "{:11}${}, {}", op, rt, rd)
+                        write!(f, "{:11}${}, {}", op, reg(rt), rd)
                     } else {
                         write!(
                             f,
@@ -514,30 +688,46 @@ impl fmt::Display for Instruction {
                             bytes.first().unwrap(),
                             "#",
                             op,
-                            rt,
+                            reg(rt),
                             *rd as u32
                         )
                     }
                 }
                 R::Cfc1 | R::Ctc1 | R::Dmfc1 | R::Dmtc1 | R::Mfc1 | R::Mtc1 => {
-                    write!(f, "{:11}${}, ${}", op, rt, FloatRegister::from(*rd))
+                    write!(f, "{:11}${}, ${}", op, reg(rt), freg(rd))
                 }
-                R::Eret | R::Tlbp | R::Tlbr | R::Tlbwi | R::Tlbwr => {
+                R::Deret | R::Eret | R::Tlbp | R::Tlbr | R::Tlbwi | R::Tlbwr => {
                     write!(f, "{}", op)
                 }
-                R::AddS | R::AddD | R::SubS | R::SubD | R::MulS | R::MulD | R::DivS | R::DivD => {
+                R::Di | R::Ei => {
+                    if let Register::Zero = rt {
+                        write!(f, "{}", op)
+                    } else {
+                        write!(f, "{:11}${}", op, reg(rt))
+                    }
+                }
+                R::AddS
+                | R::AddD
+                | R::AddPs
+                | R::SubS
+                | R::SubD
+                | R::SubPs
+                | R::MulS
+                | R::MulD
+                | R::MulPs
+                | R::DivS
+                | R::DivD
+                | R::CvtPsS
+                | R::PllPs
+                | R::PluPs
+                | R::PulPs
+                | R::PuuPs => {
                     let x = op.to_string().replace('_', ".");
-                    write!(
-                        f,
-                        "{:11}${}, ${}, ${}",
-                        x,
-                        FloatRegister::from(*rd),
-                        FloatRegister::from(*rs),
-                        FloatRegister::from(*rt)
-                    )
+                    write!(f, "{:11}${}, ${}, ${}", x, freg(rd), freg(rs), freg(rt))
                 }
                 R::AbsS
                 | R::AbsD
+                | R::AbsPs
                 | R::CvtDS
                 | R::CvtDW
                 | R::CvtDL
@@ -546,33 +736,24 @@ impl fmt::Display for Instruction {
                 | R::CvtSD
                 | R::CvtSW
                 | R::CvtSL
+                | R::CvtSPl
+                | R::CvtSPu
                 | R::CvtWD
                 | R::CvtWS
                 | R::MovS
                 | R::MovD
-                | R::MovN
+                | R::MovPs
                 | R::NegS
                 | R::NegD
+                | R::NegPs
                 | R::SqrtS
                 | R::SqrtD => {
                     let x = op.to_string().replace('_', ".");
-                    write!(
-                        f,
-                        "{:11}${}, ${}",
-                        x,
-                        FloatRegister::from(*rd),
-                        FloatRegister::from(*rs)
-                    )
+                    write!(f, "{:11}${}, ${}", x, freg(rd), freg(rs))
                 }
                 R::CeilLS | R::CeilLD | R::CeilWS | R::CeilWD => {
                     let x = op.to_string().replace('_', ".");
-                    write!(
-                        f,
-                        "{}    ${}, ${}",
-                        x,
-                        FloatRegister::from(*rd),
-                        FloatRegister::from(*rs)
-                    )
+                    write!(f, "{}    ${}, ${}", x, freg(rd), freg(rs))
                 }
                 R::FloorLS
                 | R::FloorLD
@@ -587,13 +768,7 @@ impl fmt::Display for Instruction {
                 | R::TruncWS
                 | R::TruncWD => {
                     let x = op.to_string().replace('_', ".");
-                    write!(
-                        f,
-                        "{}   ${}, ${}",
-                        x,
-                        FloatRegister::from(*rd),
-                        FloatRegister::from(*rs)
-                    )
+                    write!(f, "{}   ${}, ${}", x, freg(rd), freg(rs))
                 }
                 R::Cs => {
                     if *sa == 9 {
@@ -601,16 +776,16 @@ impl fmt::Display for Instruction {
                             f,
                             "c.{}.s    ${}, ${}",
                             FloatCond::try_from(*sa).unwrap(),
-                            FloatRegister::from(*rs),
-                            FloatRegister::from(*rt)
+                            freg(rs),
+                            freg(rt)
                         )
                     } else {
                         write!(
                             f,
                             "c.{}.s\t    ${}, ${}",
                             FloatCond::try_from(*sa).unwrap(),
-                            FloatRegister::from(*rs),
-                            FloatRegister::from(*rt)
+                            freg(rs),
+                            freg(rt)
                         )
                     }
                 }
@@ -620,33 +795,88 @@ impl fmt::Display for Instruction {
                             f,
                             "c.{}.d    ${}, ${}",
                             FloatCond::try_from(*sa).unwrap(),
-                            FloatRegister::from(*rs),
-                            FloatRegister::from(*rt)
+                            freg(rs),
+                            freg(rt)
                         )
                     } else {
                         write!(
                             f,
                             "c.{}.d\t    ${}, ${}",
                             FloatCond::try_from(*sa).unwrap(),
-                            FloatRegister::from(*rs),
-                            FloatRegister::from(*rt)
+                            freg(rs),
+                            freg(rt)
                         )
                     }
                 }
+                R::Cps => {
+                    write!(
+                        f,
+                        "{:11}${}, ${}",
+                        format!("c.{}.ps", FloatCond::try_from(*sa).unwrap()),
+                        freg(rs),
+                        freg(rt)
+                    )
+                }
                 e => panic!("{:?} not implemented", e),
             },
             Instruction::Bytes { bytes } => write!(f, "{:11}0x{:08x}", ".word", bytes),
+            Instruction::Fill { bytes, count } => {
+                write!(f, "{:11}{:#x}, {:#x}", ".space", count * 4, bytes & 0xFF)
+            }
             _ => panic!("Invalid instruction: {:?}", 0),
         }
     }
 }
 
-impl fmt::Debug for Instruction {
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_source(f, false)
+    }
+}
+
+pub(crate) struct Styled<'a>(&'a Instruction, bool);
+
+impl fmt::Display for Styled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_source(f, self.1)
+    }
+}
+
+impl fmt::Debug for Styled<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", SPACE_RE.replace_all(&self.to_string(), " "))
     }
 }
 
+// Pads the mnemonic at the start of a line of disassembly to `width` instead of 11 columns
+pub(crate) fn align_mnemonic(line: &str, width: MnemonicWidth) -> String {
+    let Some((mnemonic, operands)) = line.split_once(char::is_whitespace) else {
+        return line.to_string();
+    };
+    let operands = operands.trim_start();
+    // Annotations after an instruction without operands stay where they are
+    if operands.starts_with('#') {
+        return line.to_string();
+    }
+    match width {
+        MnemonicWidth::Columns(width) => {
+            format!(
+                "{:w$}{}",
+                mnemonic,
+                operands,
+                w = width.max(mnemonic.len() + 1)
+            )
+        }
+        MnemonicWidth::Tab => format!("{}\t{}", mnemonic, operands),
+    }
+}
+
+impl fmt::Debug for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.styled(false))
+    }
+}
+
 #[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
 #[strum(serialize_all = "snake_case")]
 pub enum Register {
@@ -692,6 +922,19 @@ impl Register {
     pub fn as_num(&self) -> u32 {
         *self as u32
     }
+
+    /// The name of the register by number, without the leading `$`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Register;
+    ///
+    /// assert_eq!(Register::T0.numeric_name(), "8");
+    /// ```
+    pub fn numeric_name(&self) -> String {
+        self.as_num().to_string()
+    }
 }
 
 impl TryFrom<u32> for Register {
@@ -743,8 +986,10 @@ impl FromStr for Register {
         let r = reg.trim().trim_start_matches('$');
 
         if REG_RE.find(r).is_some() {
-            let r = r.trim_start_matches('r');
-            let r = r.parse::<u32>().unwrap();
+            let r = r
+                .trim_start_matches('r')
+                .parse::<u32>()
+                .map_err(|_| RegParseError::RegParseError(reg.to_string()))?;
             return Register::try_from(r);
         }
 
@@ -790,9 +1035,45 @@ impl FromStr for Register {
     }
 }
 
+/// Maps a floating point register to the general purpose register with the same number.
+///
+/// Both register files have exactly 32 entries, so this conversion never fails.
 impl From<FloatRegister> for Register {
     fn from(reg: FloatRegister) -> Self {
-        Register::try_from(reg as u32).unwrap()
+        match reg {
+            FloatRegister::Fv0 => Register::Zero,
+            FloatRegister::Fv0f => Register::At,
+            FloatRegister::Fv1 => Register::V0,
+            FloatRegister::Fv1f => Register::V1,
+            FloatRegister::Ft0 => Register::A0,
+            FloatRegister::Ft0f => Register::A1,
+            FloatRegister::Ft1 => Register::A2,
+            FloatRegister::Ft1f => Register::A3,
+            FloatRegister::Ft2 => Register::T0,
+            FloatRegister::Ft2f => Register::T1,
+            FloatRegister::Ft3 => Register::T2,
+            FloatRegister::Ft3f => Register::T3,
+            FloatRegister::Fa0 => Register::T4,
+            FloatRegister::Fa0f => Register::T5,
+            FloatRegister::Fa1 => Register::T6,
+            FloatRegister::Fa1f => Register::T7,
+            FloatRegister::Ft4 => Register::S0,
+            FloatRegister::Ft4f => Register::S1,
+            FloatRegister::Ft5 => Register::S2,
+            FloatRegister::Ft5f => Register::S3,
+            FloatRegister::Fs0 => Register::S4,
+            FloatRegister::Fs0f => Register::S5,
+            FloatRegister::Fs1 => Register::S6,
+            FloatRegister::Fs1f => Register::S7,
+            FloatRegister::Fs2 => Register::T8,
+            FloatRegister::Fs2f => Register::T9,
+            FloatRegister::Fs3 => Register::K0,
+            FloatRegister::Fs3f => Register::K1,
+            FloatRegister::Fs4 => Register::Gp,
+            FloatRegister::Fs4f => Register::Sp,
+            FloatRegister::Fs5 => Register::Fp,
+            FloatRegister::Fs5f => Register::Ra,
+        }
     }
 }
 
@@ -839,6 +1120,21 @@ pub enum FloatRegister {
     Fs5f,
 }
 
+impl FloatRegister {
+    /// The name of the register by number, without the leading `$`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::FloatRegister;
+    ///
+    /// assert_eq!(FloatRegister::Ft0.numeric_name(), "f4");
+    /// ```
+    pub fn numeric_name(&self) -> String {
+        format!("f{}", *self as u32)
+    }
+}
+
 impl TryFrom<u32> for FloatRegister {
     type Error = RegParseError;
 
@@ -934,9 +1230,45 @@ impl FromStr for FloatRegister {
     }
 }
 
+/// Maps a general purpose register to the floating point register with the same number.
+///
+/// Both register files have exactly 32 entries, so this conversion never fails.
 impl From<Register> for FloatRegister {
     fn from(reg: Register) -> Self {
-        FloatRegister::try_from(reg as u32).unwrap()
+        match reg {
+            Register::Zero => FloatRegister::Fv0,
+            Register::At => FloatRegister::Fv0f,
+            Register::V0 => FloatRegister::Fv1,
+            Register::V1 => FloatRegister::Fv1f,
+            Register::A0 => FloatRegister::Ft0,
+            Register::A1 => FloatRegister::Ft0f,
+            Register::A2 => FloatRegister::Ft1,
+            Register::A3 => FloatRegister::Ft1f,
+            Register::T0 => FloatRegister::Ft2,
+            Register::T1 => FloatRegister::Ft2f,
+            Register::T2 => FloatRegister::Ft3,
+            Register::T3 => FloatRegister::Ft3f,
+            Register::T4 => FloatRegister::Fa0,
+            Register::T5 => FloatRegister::Fa0f,
+            Register::T6 => FloatRegister::Fa1,
+            Register::T7 => FloatRegister::Fa1f,
+            Register::S0 => FloatRegister::Ft4,
+            Register::S1 => FloatRegister::Ft4f,
+            Register::S2 => FloatRegister::Ft5,
+            Register::S3 => FloatRegister::Ft5f,
+            Register::S4 => FloatRegister::Fs0,
+            Register::S5 => FloatRegister::Fs0f,
+            Register::S6 => FloatRegister::Fs1,
+            Register::S7 => FloatRegister::Fs1f,
+            Register::T8 => FloatRegister::Fs2,
+            Register::T9 => FloatRegister::Fs2f,
+            Register::K0 => FloatRegister::Fs3,
+            Register::K1 => FloatRegister::Fs3f,
+            Register::Gp => FloatRegister::Fs4,
+            Register::Sp => FloatRegister::Fs4f,
+            Register::Fp => FloatRegister::Fs5,
+            Register::Ra => FloatRegister::Fs5f,
+        }
     }
 }
 
@@ -1058,12 +1390,22 @@ impl TryFrom<Register> for Cop0Register {
     }
 }
 
-#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq, Eq)]
+/// The operation of an [`Instruction::Immediate`].
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::{ITypeOp, IntoEnumIterator};
+///
+/// assert!(ITypeOp::iter().any(|op| op.to_string() == "addiu"));
+/// ```
+#[derive(Clone, Copy, Debug, Display, EnumIter, EnumString, PartialEq, Eq)]
 #[strum(ascii_case_insensitive)]
 #[strum(serialize_all = "snake_case")]
 pub enum ITypeOp {
     Addi,
     Addiu,
+    Addiupc,
     Andi,
     Bc0f,
     Bc0fl,
@@ -1106,9 +1448,11 @@ pub enum ITypeOp {
     Lw,
     Lwc1,
     Lwl,
+    Lwpc,
     Lwr,
     Lwu,
     Ori,
+    Pref,
     Sb,
     Sc,
     Scd,
@@ -1123,6 +1467,7 @@ pub enum ITypeOp {
     Swc1,
     Swl,
     Swr,
+    Synci,
     Teqi,
     Tgei,
     Tgeiu,
@@ -1163,7 +1508,8 @@ pub enum ITypeOp {
     Subiu,
 }
 
-#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq, Eq)]
+/// The operation of an [`Instruction::Jump`].
+#[derive(Clone, Copy, Debug, Display, EnumIter, EnumString, PartialEq, Eq)]
 #[strum(ascii_case_insensitive)]
 #[strum(serialize_all = "snake_case")]
 pub enum JTypeOp {
@@ -1171,7 +1517,17 @@ pub enum JTypeOp {
     Jal,
 }
 
-#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq, Eq)]
+/// The operation of an [`Instruction::Register`].
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::{IntoEnumIterator, RTypeOp};
+///
+/// let mnemonics: Vec<String> = RTypeOp::iter().map(|op| op.to_string()).collect();
+/// assert!(mnemonics.contains(&String::from("add.s")));
+/// ```
+#[derive(Clone, Copy, Debug, Display, EnumIter, EnumString, PartialEq, Eq)]
 #[strum(ascii_case_insensitive)]
 #[strum(serialize_all = "snake_case")]
 pub enum RTypeOp {
@@ -1179,18 +1535,24 @@ pub enum RTypeOp {
     AbsS,
     #[strum(to_string = "abs.d")]
     AbsD,
+    #[strum(to_string = "abs.ps")]
+    AbsPs,
     Add,
     Addu,
     #[strum(to_string = "add.s")]
     AddS,
     #[strum(to_string = "add.d")]
     AddD,
+    #[strum(to_string = "add.ps")]
+    AddPs,
     And,
     Break,
     #[strum(to_string = "c.s")]
     Cs,
     #[strum(to_string = "c.d")]
     Cd,
+    #[strum(to_string = "c.ps")]
+    Cps,
     #[strum(to_string = "ceil.l.s")]
     CeilLS,
     #[strum(to_string = "ceil.l.d")]
@@ -1201,6 +1563,9 @@ pub enum RTypeOp {
     CeilWD,
     Cfc0,
     Cfc1,
+    Cop0,
+    Cop1,
+    Cop2,
     Ctc0,
     Ctc1,
     #[strum(to_string = "cvt.d.s")]
@@ -1219,6 +1584,12 @@ pub enum RTypeOp {
     CvtSW,
     #[strum(to_string = "cvt.s.l")]
     CvtSL,
+    #[strum(to_string = "cvt.ps.s")]
+    CvtPsS,
+    #[strum(to_string = "cvt.s.pl")]
+    CvtSPl,
+    #[strum(to_string = "cvt.s.pu")]
+    CvtSPu,
     #[strum(to_string = "cvt.w.s")]
     CvtWS,
     #[strum(to_string = "cvt.w.d")]
@@ -1227,6 +1598,8 @@ pub enum RTypeOp {
     Daddu,
     Ddiv,
     Ddivu,
+    Deret,
+    Di,
     Div,
     Divu,
     #[strum(to_string = "div.s")]
@@ -1250,6 +1623,8 @@ pub enum RTypeOp {
     Dsrlv,
     Dsub,
     Dsubu,
+    Ehb,
+    Ei,
     Eret,
     #[strum(to_string = "floor.l.s")]
     FloorLS,
@@ -1260,7 +1635,11 @@ pub enum RTypeOp {
     #[strum(to_string = "floor.w.d")]
     FloorWD,
     Jalr,
+    #[strum(to_string = "jalr.hb")]
+    JalrHb,
     Jr,
+    #[strum(to_string = "jr.hb")]
+    JrHb,
     Mfc0,
     Mfc1,
     Mfhi,
@@ -1269,24 +1648,41 @@ pub enum RTypeOp {
     MovS,
     #[strum(to_string = "mov.d")]
     MovD,
+    #[strum(to_string = "mov.ps")]
+    MovPs,
     #[strum(to_string = "movn")]
     MovN,
     Mtc0,
     Mtc1,
     Mthi,
     Mtlo,
+    Mul,
     #[strum(to_string = "mul.s")]
     MulS,
     #[strum(to_string = "mul.d")]
     MulD,
+    #[strum(to_string = "mul.ps")]
+    MulPs,
     Mult,
     Multu,
     #[strum(to_string = "neg.s")]
     NegS,
     #[strum(to_string = "neg.d")]
     NegD,
+    #[strum(to_string = "neg.ps")]
+    NegPs,
     Nor,
     Or,
+    Pause,
+    #[strum(to_string = "pll.ps")]
+    PllPs,
+    #[strum(to_string = "plu.ps")]
+    PluPs,
+    #[strum(to_string = "pul.ps")]
+    PulPs,
+    #[strum(to_string = "puu.ps")]
+    PuuPs,
+    Rdpgpr,
     #[strum(to_string = "round.l.s")]
     RoundLS,
     #[strum(to_string = "round.l.d")]
@@ -1307,12 +1703,15 @@ pub enum RTypeOp {
     Srav,
     Srl,
     Srlv,
+    Ssnop,
     Sub,
     Subu,
     #[strum(to_string = "sub.s")]
     SubS,
     #[strum(to_string = "sub.d")]
     SubD,
+    #[strum(to_string = "sub.ps")]
+    SubPs,
     Sync,
     Syscall,
     Teq,
@@ -1333,6 +1732,8 @@ pub enum RTypeOp {
     TruncWS,
     #[strum(to_string = "trunc.w.d")]
     TruncWD,
+    Wait,
+    Wrpgpr,
     Xor,
     // pseudoinstructions
     Abs,
@@ -1350,7 +1751,9 @@ pub enum RTypeOp {
     Drol,
     Dror,
     Move,
-    Mul,
+    // `mul` expanded to `mult`/`mflo` for ISAs that predate the SPECIAL2 encoding
+    #[strum(to_string = "mul")]
+    MulMflo,
     Mulu,
     Mulo,
     Mulou,
@@ -1370,7 +1773,16 @@ pub enum RTypeOp {
     Sne,
 }
 
-#[derive(Clone, Copy, Debug, Display, EnumString)]
+/// The condition of a `c.cond.fmt` floating point comparison.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::{FloatCond, IntoEnumIterator};
+///
+/// assert_eq!(FloatCond::iter().count(), 16);
+/// ```
+#[derive(Clone, Copy, Debug, Display, EnumIter, EnumString)]
 #[strum(ascii_case_insensitive)]
 #[strum(serialize_all = "snake_case")]
 pub enum FloatCond {
@@ -1,14 +1,15 @@
 extern crate yaml_rust;
 
 use clap::{Parser, ValueEnum};
-use mipsasm::{get_bytes, Mipsasm};
+use mipsasm::{Isa, Mipsasm, MnemonicWidth, Options, Severity};
 use std::collections::HashMap;
 use std::error;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
-use yaml_rust::YamlLoader;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use yaml_rust::{Yaml, YamlLoader};
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -19,15 +20,94 @@ struct Cli {
     /// Write output to this file
     #[clap(short, value_parser, value_name = "output")]
     output_file: Option<PathBuf>,
-    /// Import symbols from this file
+    /// Write assembled output in this format. Defaults to hex for `.hex` and `.ihex` files, srec
+    /// for `.srec` and `.s19` files, json for `.json` files and bin otherwise. JSON output requires
+    /// the `serde` feature
+    #[clap(long, value_enum, value_name = "format")]
+    format: Option<Format>,
+    /// Import symbols from this file, either a YAML map of addresses to names or the output of `nm`
+    /// or `objdump -t`
     #[clap(short, value_parser, value_name = "syms")]
     syms: Option<PathBuf>,
+    /// Import syscall and trap code names from this file
+    #[clap(short, value_parser, value_name = "annotations")]
+    annotations: Option<PathBuf>,
     /// Use this file as input
     #[clap(value_parser)]
     input_file: PathBuf,
     /// Use this address as the base address of the program
     #[clap(default_value_t = String::from("0x80000000"), short, value_parser, value_name = "base addr")]
     base_addr: String,
+    /// Pad the output file so its size is a multiple of this power of two
+    #[clap(long, value_parser = parse_power_of_two, value_name = "align")]
+    pad_align: Option<usize>,
+    /// Fill gaps and padding with this byte instead of zero
+    #[clap(long, default_value_t = 0, value_parser = parse_byte, value_name = "byte")]
+    fill_byte: u8,
+    /// Write the label table to this file as JSON
+    #[cfg(feature = "serde")]
+    #[clap(long, value_parser, value_name = "file")]
+    emit_syms_json: Option<PathBuf>,
+    /// Write the assembled program to this file as source, with every pseudo-instruction replaced by
+    /// the instructions it expands to
+    #[clap(long, value_parser, value_name = "file")]
+    emit_expanded: Option<PathBuf>,
+    /// Write a CSV file mapping every assembled word to its address and source line
+    #[clap(long, value_parser, value_name = "file")]
+    map: Option<PathBuf>,
+    /// Print how many words came from instructions, pseudo-instruction expansions, data and padding
+    /// to stderr when assembling
+    #[clap(long)]
+    stats: bool,
+    /// Exit with an error if any word could not be disassembled
+    #[clap(long)]
+    strict: bool,
+    /// Assemble for this instruction set, unless overridden with `.set mipsN`. One of mips1, mips2,
+    /// mips3, mips4, mips5, mips32, mips32r2, mips32r6, mips64, mips64r2 or mips64r6. Defaults to
+    /// accepting every supported instruction
+    #[clap(long, value_parser, value_name = "isa")]
+    isa: Option<Isa>,
+    /// Skip unknown `.set` options with a warning instead of failing
+    #[clap(long)]
+    allow_unknown_set: bool,
+    /// Zero references to undefined labels with a warning instead of failing
+    #[clap(long)]
+    allow_unresolved: bool,
+    /// Treat every warning as an error
+    #[clap(long)]
+    werror: bool,
+    /// Insert a nop after every branch and jump whose delay slot doesn't already hold one, except
+    /// after `.set noreorder`
+    #[clap(long)]
+    fill_delay_slots: bool,
+    /// Start a new function at every address that has a symbol when disassembling
+    #[clap(long)]
+    split_syms: bool,
+    /// Print registers by number instead of by name when disassembling
+    #[clap(long)]
+    numeric_regs: bool,
+    /// Print branch targets as symbols like jump targets, noting the address of every target
+    /// replaced by a symbol, when disassembling
+    #[clap(long)]
+    annotate_calls: bool,
+    /// Pad mnemonics to this many columns, or separate them from their operands with a tab if
+    /// `tab`, when disassembling. Defaults to 11
+    #[clap(long, value_parser, value_name = "width")]
+    mnemonic_width: Option<MnemonicWidth>,
+    /// Only disassemble from the address of this symbol, up to the next symbol unless
+    /// `--end-label` or `--length` is given
+    #[clap(long, value_parser, value_name = "name")]
+    start_label: Option<String>,
+    /// Stop disassembling at the address of this symbol
+    #[clap(long, value_parser, value_name = "name", requires = "start_label")]
+    end_label: Option<String>,
+    /// Disassemble this many bytes from the start label
+    #[clap(long, value_parser = parse_length, value_name = "bytes", requires = "start_label", conflicts_with = "end_label")]
+    length: Option<u32>,
+    /// Emit the words from the start address up to the end address as data when disassembling. Can
+    /// be given several times
+    #[clap(long, value_parser = parse_range, value_name = "start:end")]
+    data_range: Vec<Range<u32>>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -36,6 +116,46 @@ enum Mode {
     Disasm,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Raw big-endian words
+    Bin,
+    /// Intel HEX
+    Hex,
+    /// Motorola S-records
+    Srec,
+    /// The assembled program as JSON, with its labels and the address, source line and source
+    /// form of every word
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+impl Format {
+    // Picks a format from the extension of the output file
+    fn from_path(path: &Path) -> Result<Format, String> {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Ok(match ext.as_str() {
+            "hex" | "ihex" => Format::Hex,
+            "srec" | "s19" => Format::Srec,
+            #[cfg(feature = "serde")]
+            "json" => Format::Json,
+            // Writing binary to a `.json` file would only surprise
+            #[cfg(not(feature = "serde"))]
+            "json" => {
+                return Err(format!(
+                    "`{}` needs JSON output, which requires the `serde` feature",
+                    path.display()
+                ))
+            }
+            _ => Format::Bin,
+        })
+    }
+}
+
 fn main() -> Result<(), Box<dyn error::Error>> {
     let cli = Cli::parse();
 
@@ -44,21 +164,36 @@ fn main() -> Result<(), Box<dyn error::Error>> {
         None => String::new(),
     };
 
-    let yaml = YamlLoader::load_from_str(&syms).unwrap();
-    let syms = yaml.first().map_or_else(HashMap::new, |y| {
-        y.as_hash()
-            .map(|hash| {
-                hash.iter()
-                    .map(|(k, v)| {
-                        (
-                            k.as_i64().unwrap_or_default() as u32,
-                            v.as_str().unwrap_or_default(),
-                        )
-                    })
-                    .collect()
-            })
-            .unwrap_or_default()
+    // Symbol files are either a YAML map of addresses to names, or the output of `nm`
+    let yaml = YamlLoader::load_from_str(&syms).unwrap_or_default();
+    let syms = if syms.trim().is_empty() || yaml.first().is_some_and(|y| y.as_hash().is_some()) {
+        read_map(&yaml)
+    } else {
+        let (syms, skipped) = mipsasm::read_nm_symbols(&syms);
+        for line in skipped {
+            eprintln!(
+                "Warning: Skipping malformed symbol on line {} of `{}`",
+                line,
+                cli.syms.as_deref().unwrap().display()
+            );
+        }
+        syms
+    };
+
+    let annotations: String = match cli.annotations.as_deref() {
+        Some(annotations) => fs::read_to_string(annotations)?.parse()?,
+        None => String::new(),
+    };
+
+    let yaml = YamlLoader::load_from_str(&annotations).unwrap_or_else(|e| {
+        eprintln!(
+            "Error: Invalid annotation file `{}`: {}",
+            cli.annotations.as_deref().unwrap().display(),
+            e
+        );
+        std::process::exit(1);
     });
+    let annotations = read_map(&yaml);
 
     let addr = cli.base_addr.replace("0x", "");
     let addr = u32::from_str_radix(&addr, 16).unwrap_or_else(|_| {
@@ -69,7 +204,20 @@ fn main() -> Result<(), Box<dyn error::Error>> {
     match cli.mode {
         Mode::Asm => {
             let data: String = fs::read_to_string(cli.input_file)?.parse()?;
-            let output = match Mipsasm::new().base(addr).symbols(syms).assemble(&data) {
+            let output = match Mipsasm::new()
+                .base(addr)
+                .symbols(syms)
+                .options(Options {
+                    allow_unknown_set: cli.allow_unknown_set,
+                    allow_unresolved: cli.allow_unresolved,
+                    fill_byte: cli.fill_byte,
+                    isa: cli.isa,
+                    warnings_as_errors: cli.werror,
+                    fill_delay_slots: cli.fill_delay_slots,
+                    ..Default::default()
+                })
+                .assemble_program(&data)
+            {
                 Ok(output) => output,
                 Err(e) => {
                     for err in e {
@@ -79,15 +227,54 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                 }
             };
 
-            let output = get_bytes(&output);
+            let diagnostics = output.validate();
+            for diagnostic in &diagnostics {
+                eprintln!("{}", diagnostic);
+            }
+            if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                std::process::exit(1);
+            }
+
+            #[cfg(feature = "serde")]
+            if let Some(path) = cli.emit_syms_json {
+                fs::write(path, output.labels_json())?;
+            }
+            if let Some(path) = cli.emit_expanded {
+                fs::write(path, output.to_string())?;
+            }
+            if let Some(path) = cli.map {
+                fs::write(path, output.map())?;
+            }
+            if cli.stats {
+                eprintln!("{}", output.stats());
+            }
+
             if let Some(output_file) = cli.output_file {
-                let bytes: Vec<u8> = output
+                let format = match cli.format {
+                    Some(format) => format,
+                    None => Format::from_path(&output_file).unwrap_or_else(|e| {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }),
+                };
+                let mut bytes: Vec<u8> = output
+                    .words()
                     .iter()
                     .flat_map(|word| word.to_be_bytes().to_vec())
                     .collect();
+                if let Some(align) = cli.pad_align {
+                    bytes.resize(bytes.len().next_multiple_of(align), cli.fill_byte);
+                }
+                let bytes = match format {
+                    Format::Bin => bytes,
+                    Format::Hex => to_ihex(&bytes, addr).into_bytes(),
+                    Format::Srec => to_srec(&bytes, addr).into_bytes(),
+                    #[cfg(feature = "serde")]
+                    Format::Json => output.to_json().into_bytes(),
+                };
                 File::create(output_file)?.write_all(&bytes)?;
             } else {
-                println!("{:08X?}", output);
+                println!("{:08X?}", output.words());
             }
         }
         Mode::Disasm => {
@@ -103,7 +290,46 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     break;
                 }
             }
-            let output = Mipsasm::new().base(addr).symbols(syms).disassemble(&words);
+            let (addr, words) = match cli.start_label.as_deref() {
+                Some(start) => {
+                    let range = label_range(&syms, start, cli.end_label.as_deref(), cli.length)
+                        .unwrap_or_else(|e| {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        });
+                    // Only the part of the range that is in the file is disassembled
+                    let end = addr.saturating_add(words.len() as u32 * 4);
+                    if range.start < addr || range.start >= end {
+                        eprintln!(
+                            "Error: `{}` at 0x{:08x} is outside of the input file",
+                            start, range.start
+                        );
+                        std::process::exit(1);
+                    }
+                    let first = (range.start - addr) as usize / 4;
+                    let last = (range.end.min(end) - addr).div_ceil(4) as usize;
+                    (range.start, words[first..last.max(first)].to_vec())
+                }
+                None => (addr, words),
+            };
+            let mut mipsasm = Mipsasm::new();
+            mipsasm.base(addr).symbols(syms).annotations(annotations);
+            if cli.split_syms {
+                mipsasm.split_at_symbols();
+            }
+            if cli.numeric_regs {
+                mipsasm.numeric_regs();
+            }
+            if cli.annotate_calls {
+                mipsasm.annotate_calls();
+            }
+            if let Some(width) = cli.mnemonic_width {
+                mipsasm.mnemonic_width(width);
+            }
+            for range in cli.data_range {
+                mipsasm.data_range(range);
+            }
+            let (output, errors) = mipsasm.disassemble_checked(&words);
 
             if let Some(output_file) = cli.output_file {
                 let mut f = File::create(output_file)?;
@@ -115,7 +341,237 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     println!("{}", inst);
                 }
             }
+
+            if !errors.is_empty() {
+                for err in &errors {
+                    eprintln!("{}", err);
+                }
+                eprintln!(
+                    "{} of {} words could not be decoded",
+                    errors.len(),
+                    words.len()
+                );
+                if cli.strict {
+                    std::process::exit(1);
+                }
+            }
         }
     }
     Ok(())
 }
+
+// Reads a YAML map of numbers to names, as used by the symbol and annotation files
+fn read_map(yaml: &[Yaml]) -> HashMap<u32, &str> {
+    yaml.first().map_or_else(HashMap::new, |y| {
+        y.as_hash()
+            .map(|hash| {
+                hash.iter()
+                    .map(|(k, v)| {
+                        (
+                            k.as_i64().unwrap_or_default() as u32,
+                            v.as_str().unwrap_or_default(),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    })
+}
+
+fn parse_power_of_two(s: &str) -> Result<usize, String> {
+    let n = match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|_| format!("`{}` is not a number", s))?;
+    if !n.is_power_of_two() {
+        return Err(format!("`{}` is not a power of two", s));
+    }
+    Ok(n)
+}
+
+fn parse_byte(s: &str) -> Result<u8, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|_| format!("`{}` is not a byte", s))
+}
+
+// Writes bytes loaded at `base` as Intel HEX records of up to 16 bytes
+fn to_ihex(bytes: &[u8], base: u32) -> String {
+    let mut out = String::new();
+    let mut upper = None;
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let addr = base.wrapping_add(offset as u32);
+        // Extended linear address record for the upper 16 bits of the address
+        if upper != Some(addr >> 16) {
+            upper = Some(addr >> 16);
+            out.push_str(&ihex_record(0, 4, &(addr >> 16).to_be_bytes()[2..]));
+        }
+        // A record only holds the lower 16 bits of its address, so it can't cross into the next
+        // 64K segment
+        let len = (bytes.len() - offset)
+            .min(16)
+            .min(0x10000 - (addr & 0xffff) as usize);
+        out.push_str(&ihex_record(addr as u16, 0, &bytes[offset..offset + len]));
+        offset += len;
+    }
+    out.push_str(&ihex_record(0, 1, &[]));
+    out
+}
+
+fn ihex_record(addr: u16, kind: u8, data: &[u8]) -> String {
+    let mut record = vec![data.len() as u8];
+    record.extend(addr.to_be_bytes());
+    record.push(kind);
+    record.extend(data);
+    let sum = record.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    record.push(sum.wrapping_neg());
+    let hex: String = record.iter().map(|b| format!("{:02X}", b)).collect();
+    format!(":{}\n", hex)
+}
+
+// Writes bytes loaded at `base` as S3 records of 16 bytes, with `base` as the start address
+fn to_srec(bytes: &[u8], base: u32) -> String {
+    let mut out = srec_record(0, &[0; 2], b"mipsasm");
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        let addr = base.wrapping_add(i as u32 * 16);
+        out.push_str(&srec_record(3, &addr.to_be_bytes(), chunk));
+    }
+    out.push_str(&srec_record(7, &base.to_be_bytes(), &[]));
+    out
+}
+
+fn srec_record(kind: u8, addr: &[u8], data: &[u8]) -> String {
+    let mut record = vec![(addr.len() + data.len() + 1) as u8];
+    record.extend(addr);
+    record.extend(data);
+    let sum = record.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    record.push(!sum);
+    let hex: String = record.iter().map(|b| format!("{:02X}", b)).collect();
+    format!("S{}{}\n", kind, hex)
+}
+
+// Finds the addresses of the symbols that `--start-label` and `--end-label` name. Without an end
+// label or a length, the range ends at the next symbol
+fn label_range(
+    syms: &HashMap<u32, &str>,
+    start: &str,
+    end: Option<&str>,
+    length: Option<u32>,
+) -> Result<Range<u32>, String> {
+    let find = |name: &str| {
+        syms.iter()
+            .find(|(_, sym)| **sym == name)
+            .map(|(addr, _)| *addr)
+            .ok_or_else(|| format!("Unknown symbol `{}`", name))
+    };
+    let start_addr = find(start)?;
+    let end_addr = match (end, length) {
+        (Some(end), _) => find(end)?,
+        (None, Some(length)) => start_addr.saturating_add(length),
+        (None, None) => syms
+            .keys()
+            .copied()
+            .filter(|addr| *addr > start_addr)
+            .min()
+            .unwrap_or(u32::MAX),
+    };
+    if end_addr < start_addr {
+        return Err(format!("`{}` comes before `{}`", end.unwrap(), start));
+    }
+    Ok(start_addr..end_addr)
+}
+
+fn parse_length(s: &str) -> Result<u32, String> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|_| format!("`{}` is not a number", s))
+}
+
+fn parse_range(s: &str) -> Result<Range<u32>, String> {
+    let parse = |n: &str| match n.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => n.parse(),
+    };
+    let (start, end) = s
+        .split_once(':')
+        .ok_or_else(|| format!("`{}` is not of the form start:end", s))?;
+    let start = parse(start).map_err(|_| format!("`{}` is not an address", start))?;
+    let end = parse(end).map_err(|_| format!("`{}` is not an address", end))?;
+    if start > end {
+        return Err(format!("`{}` ends before it starts", s));
+    }
+    Ok(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        let format = |path: &str| Format::from_path(Path::new(path));
+        assert!(format("out.HEX") == Ok(Format::Hex));
+        assert!(format("out.s19") == Ok(Format::Srec));
+        assert!(format("out.z64") == Ok(Format::Bin));
+        #[cfg(feature = "serde")]
+        assert!(format("out.json") == Ok(Format::Json));
+        #[cfg(not(feature = "serde"))]
+        assert!(format("out.json").is_err());
+    }
+
+    #[test]
+    fn test_ihex() {
+        let program = [0x03, 0xe0, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(
+            to_ihex(&program, 0x80000000),
+            ":0200000480007A\n:0800000003E00008000000000D\n:00000001FF\n"
+        );
+        assert_eq!(to_ihex(&[], 0x80000000), ":00000001FF\n");
+    }
+
+    #[test]
+    fn test_ihex_segment_boundary() {
+        // Records are split where the upper 16 bits of the address change
+        assert_eq!(
+            to_ihex(&[0x11; 16], 0x8000fff8),
+            ":0200000480007A\n\
+             :08FFF800111111111111111179\n\
+             :02000004800179\n\
+             :08000000111111111111111170\n\
+             :00000001FF\n"
+        );
+        assert_eq!(
+            to_ihex(&[0x11; 24], 0x8000fffc),
+            ":0200000480007A\n\
+             :04FFFC0011111111BD\n\
+             :02000004800179\n\
+             :1000000011111111111111111111111111111111E0\n\
+             :0400100011111111A8\n\
+             :00000001FF\n"
+        );
+    }
+
+    #[test]
+    fn test_srec() {
+        let program = [0x03, 0xe0, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00];
+        assert_eq!(
+            to_srec(&program, 0x80000000),
+            "S00A00006D69707361736DFB\n\
+             S30D8000000003E000080000000087\n\
+             S705800000007A\n"
+        );
+        assert_eq!(
+            to_srec(&[0x11; 20], 0x80000000),
+            "S00A00006D69707361736DFB\n\
+             S31580000000111111111111111111111111111111115A\n\
+             S309800000101111111122\n\
+             S705800000007A\n"
+        );
+    }
+}
@@ -41,6 +41,51 @@ macro_rules! error {
             found: $found,
         }
     };
+    ($self:ident, TooManyOperands, $ops:expr, $max:expr, $found:expr) => {
+        ParserError::TooManyOperands {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            ops: $ops.to_string(),
+            max: $max,
+            found: $found,
+        }
+    };
+    ($self:ident, AddressOverflow) => {
+        ParserError::AddressOverflow {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+        }
+    };
+    ($self:ident, OutputTooLarge, $size:expr, $max:expr) => {
+        ParserError::OutputTooLarge {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            size: $size,
+            max: $max,
+        }
+    };
+    ($self:ident, LineTooLong, $length:expr, $max:expr) => {
+        ParserError::LineTooLong {
+            line: Line::new(
+                $self.line_num,
+                $self
+                    .input
+                    .get($self.line_num - 1)
+                    .unwrap()
+                    .chars()
+                    .take(32)
+                    .collect(),
+            ),
+            length: $length,
+            max: $max,
+        }
+    };
     ($self:ident, InvalidOpcode, $opcode:expr) => {
         ParserError::InvalidOpcode {
             line: Line::new(
@@ -50,6 +95,16 @@ macro_rules! error {
             opcode: $opcode.to_string(),
         }
     };
+    ($self:ident, InvalidFormat, $opcode:expr, $format:expr) => {
+        ParserError::InvalidFormat {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            opcode: $opcode.to_string(),
+            format: $format.to_string(),
+        }
+    };
     ($self:ident, InvalidRegister, $register:expr) => {
         ParserError::InvalidRegister {
             line: Line::new(
@@ -77,6 +132,15 @@ macro_rules! error {
             immediate: $immediate.to_string(),
         }
     };
+    ($self:ident, InvalidString, $string:expr) => {
+        ParserError::InvalidString {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            string: $string.to_string(),
+        }
+    };
     ($self:ident, InvalidFloatCond, $cond:expr) => {
         ParserError::InvalidFloatCond {
             line: Line::new(
@@ -86,6 +150,15 @@ macro_rules! error {
             cond: $cond.to_string(),
         }
     };
+    ($self:ident, UnknownOperator, $operator:expr) => {
+        ParserError::UnknownOperator {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            operator: $operator.to_string(),
+        }
+    };
     ($self:ident, BranchOutOfBounds, $line:expr, $target:expr, $bounds:expr) => {
         ParserError::BranchOutOfBounds {
             line: Line::new(
@@ -96,6 +169,16 @@ macro_rules! error {
             bounds: $bounds,
         }
     };
+    ($self:ident, OrgBackwards, $target:expr, $addrs:expr) => {
+        ParserError::OrgBackwards {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            target: $target.to_string(),
+            addrs: $addrs,
+        }
+    };
     ($self:ident, LocalLabelOutOfScope, $line_num:expr, $label:expr) => {
         ParserError::LocalLabelOutOfScope {
             line: Line::new(
@@ -105,6 +188,33 @@ macro_rules! error {
             label: $label.to_string(),
         }
     };
+    ($self:ident, InvalidSetOption, $option:expr) => {
+        ParserError::InvalidSetOption {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            option: $option.to_string(),
+        }
+    };
+    ($self:ident, CompressedIsa, $option:expr) => {
+        ParserError::CompressedIsa {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            option: $option.to_string(),
+        }
+    };
+    ($self:ident, UnmatchedSet, $line_num:expr, $option:expr) => {
+        ParserError::UnmatchedSet {
+            line: Line::new(
+                $line_num,
+                $self.input.get($line_num - 1).unwrap().to_string(),
+            ),
+            option: $option.to_string(),
+        }
+    };
     ($self:ident, UndefinedLabel, $line_num:expr, $label:expr) => {
         ParserError::UndefinedLabel {
             line: Line::new(
@@ -139,6 +249,33 @@ macro_rules! warning {
             offset: $target,
         }
     };
+    ($self:ident, UnresolvedReference, $line_num:expr, $label:expr, $offset:expr) => {
+        ParserWarning::UnresolvedReference {
+            line: Line::new(
+                $line_num,
+                $self.input.get($line_num - 1).unwrap().to_string(),
+            ),
+            label: $label.to_string(),
+            offset: $offset,
+        }
+    };
+    ($self:ident, MissingCprestore) => {
+        ParserWarning::MissingCprestore {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+        }
+    };
+    ($self:ident, UnknownSetOption, $option:expr) => {
+        ParserWarning::UnknownSetOption {
+            line: Line::new(
+                $self.line_num,
+                $self.input.get($self.line_num - 1).unwrap().to_string(),
+            ),
+            option: $option.to_string(),
+        }
+    };
     ($self:ident, UnalignedJump, $target:expr) => {
         ParserWarning::UnalignedJump {
             line: Line::new(
@@ -164,9 +301,30 @@ impl Line {
 
 #[derive(Debug)]
 pub enum ParserWarning {
-    InvalidInstructionInDelaySlot { line: Line, delay_slot_inst: Line },
-    UnalignedBranch { line: Line, offset: String },
-    UnalignedJump { line: Line, target: String },
+    InvalidInstructionInDelaySlot {
+        line: Line,
+        delay_slot_inst: Line,
+    },
+    MissingCprestore {
+        line: Line,
+    },
+    UnalignedBranch {
+        line: Line,
+        offset: String,
+    },
+    UnalignedJump {
+        line: Line,
+        target: String,
+    },
+    UnresolvedReference {
+        line: Line,
+        label: String,
+        offset: u32,
+    },
+    UnknownSetOption {
+        line: Line,
+        option: String,
+    },
 }
 
 impl fmt::Display for ParserWarning {
@@ -238,6 +396,28 @@ impl fmt::Display for ParserWarning {
                     )
                 )
             }
+            Self::MissingCprestore {
+                line: Line { num, content },
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "warning: call in PIC code without a preceding `.cprestore`"
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(
+                        *num,
+                        content,
+                        margin,
+                        true,
+                        "$gp cannot be reloaded after this call",
+                        false,
+                        content.trim()
+                    )
+                )
+            }
             Self::UnalignedJump {
                 line: Line { num, content },
                 target,
@@ -262,10 +442,41 @@ impl fmt::Display for ParserWarning {
                     )
                 )
             }
+            Self::UnresolvedReference {
+                line: Line { num, content },
+                label,
+                offset,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "warning: label `{}` is not defined, leaving the word at offset {:#x} zeroed",
+                    label, offset
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(*num, content, margin, true, "used here", false, label)
+                )
+            }
+            Self::UnknownSetOption {
+                line: Line { num, content },
+                option,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(f, "warning: ignoring unknown `.set` option `{}`", option)?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(*num, content, margin, true, "", false, option)
+                )
+            }
         }
     }
 }
 
+impl std::error::Error for ParserWarning {}
+
 #[derive(Debug)]
 pub enum ParserError {
     MultipleLabelDefinition {
@@ -286,10 +497,34 @@ pub enum ParserError {
         found: usize,
         ops: String,
     },
+    TooManyOperands {
+        line: Line,
+        max: usize,
+        found: usize,
+        ops: String,
+    },
+    LineTooLong {
+        line: Line,
+        length: usize,
+        max: usize,
+    },
+    OutputTooLarge {
+        line: Line,
+        size: usize,
+        max: usize,
+    },
+    AddressOverflow {
+        line: Line,
+    },
     InvalidOpcode {
         line: Line,
         opcode: String,
     },
+    InvalidFormat {
+        line: Line,
+        opcode: String,
+        format: String,
+    },
     InvalidRegister {
         line: Line,
         register: String,
@@ -302,15 +537,28 @@ pub enum ParserError {
         line: Line,
         immediate: String,
     },
+    InvalidString {
+        line: Line,
+        string: String,
+    },
     InvalidFloatCond {
         line: Line,
         cond: String,
     },
+    UnknownOperator {
+        line: Line,
+        operator: String,
+    },
     BranchOutOfBounds {
         line: Line,
         branch: String,
         bounds: (u32, u32),
     },
+    OrgBackwards {
+        line: Line,
+        target: String,
+        addrs: (u32, u32),
+    },
     LocalLabelOutOfScope {
         line: Line,
         label: String,
@@ -319,6 +567,22 @@ pub enum ParserError {
         line: Line,
         label: String,
     },
+    InvalidSetOption {
+        line: Line,
+        option: String,
+    },
+    CompressedIsa {
+        line: Line,
+        option: String,
+    },
+    UnmatchedSet {
+        line: Line,
+        option: String,
+    },
+    // A warning reported as an error by `Options::warnings_as_errors`
+    DeniedWarning {
+        warning: ParserWarning,
+    },
 }
 
 impl fmt::Display for ParserError {
@@ -416,6 +680,80 @@ impl fmt::Display for ParserError {
                     )
                 )
             }
+            Self::TooManyOperands {
+                line: Line { num, content },
+                ops,
+                max,
+                found,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: too many operands ({} found, the limit is {})",
+                    found, max
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(*num, content, margin, false, "", true, ops)
+                )
+            }
+            Self::LineTooLong {
+                line: Line { num, content },
+                length,
+                max,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: line is {} bytes long, the limit is {}",
+                    length, max
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(
+                        *num,
+                        &format!("{}...", content),
+                        margin,
+                        false,
+                        "line starts here",
+                        true,
+                        content
+                    )
+                )
+            }
+            Self::OutputTooLarge {
+                line: Line { num, content },
+                size,
+                max,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: program is {:#x} bytes long, the limit is {:#x}",
+                    size, max
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(*num, content, margin, false, "", true, content.trim())
+                )
+            }
+            Self::AddressOverflow {
+                line: Line { num, content },
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: program runs past the end of the address space"
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(*num, content, margin, false, "", true, content.trim())
+                )
+            }
             Self::InvalidOpcode {
                 line: Line { num, content },
                 opcode,
@@ -428,6 +766,32 @@ impl fmt::Display for ParserError {
                     fmt_line(*num, content, margin, false, "", true, opcode)
                 )
             }
+            Self::InvalidFormat {
+                line: Line { num, content },
+                opcode,
+                format,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: invalid format `{}` for `{}`",
+                    format, opcode
+                )?;
+                let opcode = format!("{}.{}", opcode, format);
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(
+                        *num,
+                        content,
+                        margin,
+                        false,
+                        "unsupported format",
+                        true,
+                        &opcode
+                    )
+                )
+            }
             Self::InvalidRegister {
                 line: Line { num, content },
                 register,
@@ -461,11 +825,31 @@ impl fmt::Display for ParserError {
                 immediate,
             } => {
                 let margin = num.to_string().len();
+                let digits = immediate.strip_suffix(['h', 'H']).unwrap_or_default();
+                let msg = if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                    "hex numbers ending in `h` must start with a digit, e.g. `0FFh`"
+                } else if immediate.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                    "not a number or a defined label"
+                } else {
+                    ""
+                };
                 writeln!(f, "\x1b[91merror\x1b[0m: invalid immediate `{}`", immediate)?;
                 writeln!(
                     f,
                     "{}",
-                    fmt_line(*num, content, margin, false, "", true, immediate)
+                    fmt_line(*num, content, margin, false, msg, true, immediate)
+                )
+            }
+            Self::InvalidString {
+                line: Line { num, content },
+                string,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(f, "\x1b[91merror\x1b[0m: invalid string `{}`", string)?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(*num, content, margin, false, "", true, string)
                 )
             }
             Self::InvalidFloatCond {
@@ -484,6 +868,30 @@ impl fmt::Display for ParserError {
                     fmt_line(*num, content, margin, false, "", true, cond)
                 )
             }
+            Self::UnknownOperator {
+                line: Line { num, content },
+                operator,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: unknown relocation operator `{}`",
+                    operator
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(
+                        *num,
+                        content,
+                        margin,
+                        false,
+                        "expected `%hi` or `%lo`",
+                        true,
+                        operator
+                    )
+                )
+            }
             Self::BranchOutOfBounds {
                 line: Line { num, content },
                 branch,
@@ -508,6 +916,33 @@ impl fmt::Display for ParserError {
                     )
                 )
             }
+            Self::OrgBackwards {
+                line: Line { num, content },
+                target,
+                addrs,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: `.org` cannot move the location counter backwards"
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(
+                        *num,
+                        content,
+                        margin,
+                        false,
+                        &format!(
+                            "0x{:08x} is before the current address 0x{:08x}",
+                            addrs.0, addrs.1
+                        ),
+                        true,
+                        target
+                    )
+                )
+            }
             Self::LocalLabelOutOfScope {
                 line: Line { num, content },
                 label,
@@ -536,6 +971,84 @@ impl fmt::Display for ParserError {
                     fmt_line(*num, content, margin, false, "used here", true, label)
                 )
             }
+            Self::InvalidSetOption {
+                line: Line { num, content },
+                option,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: unknown `.set` option `{}`",
+                    option
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(*num, content, margin, false, "", true, option)
+                )
+            }
+            Self::CompressedIsa {
+                line: Line { num, content },
+                option,
+            } => {
+                let margin = num.to_string().len();
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: MIPS16e and microMIPS code is not supported"
+                )?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(
+                        *num,
+                        content,
+                        margin,
+                        false,
+                        "the code after this is skipped until it is turned off",
+                        true,
+                        option
+                    )
+                )
+            }
+            Self::UnmatchedSet {
+                line: Line { num, content },
+                option,
+            } => {
+                let margin = num.to_string().len();
+                let (msg, other) = if option.eq_ignore_ascii_case("pop") {
+                    (
+                        "`.set pop` without a matching `.set push`",
+                        "no saved state to restore",
+                    )
+                } else {
+                    (
+                        "`.set push` without a matching `.set pop`",
+                        "state saved here",
+                    )
+                };
+                writeln!(f, "\x1b[91merror\x1b[0m: {}", msg)?;
+                writeln!(
+                    f,
+                    "{}",
+                    fmt_line(*num, content, margin, false, other, true, option)
+                )
+            }
+            Self::DeniedWarning { warning } => {
+                write!(f, "{}", warning)?;
+                writeln!(
+                    f,
+                    "\x1b[91merror\x1b[0m: the warning above is treated as an error"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParserError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::DeniedWarning { warning } => Some(warning),
+            _ => None,
         }
     }
 }
@@ -550,9 +1063,9 @@ fn fmt_line(
     underline: &str,
 ) -> String {
     let mut s = String::new();
-    let underline_start = content
-        .find(underline)
-        .unwrap_or_else(|| panic!("{}", content));
+    // The operand can be rewritten while parsing (e.g. `0x` stripped), so it may not appear
+    // verbatim in the line
+    let underline_start = content.find(underline).unwrap_or(0);
     if first_space {
         writeln!(s, "\x1b[94m{:>margin$} |\x1b[0m", "").unwrap();
     }
@@ -583,3 +1096,118 @@ fn fmt_line(
     write!(s, "\x1b[94m{:>margin$} |\x1b[0m", "").unwrap();
     s
 }
+
+/// A word that the disassembler could not decode into an instruction.
+///
+/// The word is still emitted as a `.word` directive in the disassembly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    /// The address of the word
+    pub addr: u32,
+    /// The word that could not be decoded
+    pub word: u32,
+}
+
+impl std::error::Error for DecodeError {}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "warning: could not decode word 0x{:08x} at address 0x{:08x}",
+            self.word, self.addr
+        )
+    }
+}
+
+/// An error from assembling a single instruction with [`Mipsasm::assemble_one`](crate::Mipsasm::assemble_one)
+/// or [`patch`](crate::patch).
+///
+/// Every other error of the crate, and I/O errors, convert into it, so that `?` can be used on all
+/// of them in a function returning this type.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::{Error, Mipsasm};
+///
+/// fn assemble(path: &str) -> Result<Vec<u32>, Error> {
+///     let src = std::fs::read_to_string(path)?;
+///     Ok(Mipsasm::new().assemble_program(&src)?.words())
+/// }
+///
+/// assert!(matches!(assemble("missing.s"), Err(Error::Io(_))));
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    /// The instruction could not be assembled
+    Parse(Vec<ParserError>),
+    /// The instruction assembled to this many words instead of one
+    NotOneWord(usize),
+    /// The address is not inside of the `len` byte buffer loaded at `base`
+    OutOfBounds { addr: u32, base: u32, len: usize },
+    /// A word could not be disassembled
+    Decode(DecodeError),
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+}
+
+impl From<Vec<ParserError>> for Error {
+    fn from(errs: Vec<ParserError>) -> Self {
+        Error::Parse(errs)
+    }
+}
+
+impl From<ParserError> for Error {
+    fn from(err: ParserError) -> Self {
+        Error::Parse(vec![err])
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Self {
+        Error::Decode(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(errs) => errs.iter().try_for_each(|e| write!(f, "{}", e)),
+            Error::NotOneWord(count) => write!(
+                f,
+                "\x1b[91merror\x1b[0m: expected exactly one instruction word, found {}",
+                count
+            ),
+            Error::OutOfBounds { addr, base, len } => write!(
+                f,
+                "\x1b[91merror\x1b[0m: address 0x{:08x} is outside of the buffer at 0x{:08x}-0x{:08x}",
+                addr,
+                base,
+                base.wrapping_add(*len as u32)
+            ),
+            Error::Decode(err) => write!(f, "{}", err),
+            Error::Io(err) => write!(f, "\x1b[91merror\x1b[0m: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            // Only the first error of a failed parse can be the source
+            Error::Parse(errs) => errs
+                .first()
+                .map(|err| err as &(dyn std::error::Error + 'static)),
+            Error::NotOneWord(_) | Error::OutOfBounds { .. } => None,
+            Error::Decode(err) => Some(err),
+            Error::Io(err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// Symbolic names for the operation and hint codes of `cache` and `pref`, which can be written in
+// place of the number. Names are matched case-insensitively.
+
+// `cache` operations, named after the operation and the cache it applies to: `I` for the primary
+// instruction cache, `D` for the primary data cache, `T` for the tertiary cache and `S` for the
+// secondary cache
+const CACHE_OPS: &[(&str, u32)] = &[
+    ("index_invalidate_i", 0x00),
+    ("index_writeback_inv_d", 0x01),
+    ("index_writeback_inv_t", 0x02),
+    ("index_writeback_inv_s", 0x03),
+    ("index_load_tag_i", 0x04),
+    ("index_load_tag_d", 0x05),
+    ("index_load_tag_t", 0x06),
+    ("index_load_tag_s", 0x07),
+    ("index_store_tag_i", 0x08),
+    ("index_store_tag_d", 0x09),
+    ("index_store_tag_t", 0x0a),
+    ("index_store_tag_s", 0x0b),
+    ("create_dirty_excl_d", 0x0d),
+    ("create_dirty_excl_s", 0x0f),
+    ("hit_invalidate_i", 0x10),
+    ("hit_invalidate_d", 0x11),
+    ("hit_invalidate_t", 0x12),
+    ("hit_invalidate_s", 0x13),
+    ("fill_i", 0x14),
+    ("hit_writeback_inv_d", 0x15),
+    ("hit_writeback_inv_t", 0x16),
+    ("hit_writeback_inv_s", 0x17),
+    ("hit_writeback_i", 0x18),
+    ("hit_writeback_d", 0x19),
+    ("hit_writeback_t", 0x1a),
+    ("hit_writeback_s", 0x1b),
+    ("fetch_lock_i", 0x1c),
+    ("fetch_lock_d", 0x1d),
+];
+
+// `pref` hints
+const PREF_HINTS: &[(&str, u32)] = &[
+    ("load", 0),
+    ("store", 1),
+    ("load_streamed", 4),
+    ("store_streamed", 5),
+    ("load_retained", 6),
+    ("store_retained", 7),
+    ("writeback_invalidate", 25),
+    ("prepare_for_store", 30),
+];
+
+// Returns the code named `name` for the hinted instruction `op`, or `None` if `op` takes no hint
+// or the name isn't known
+pub(crate) fn code(op: &str, name: &str) -> Option<u32> {
+    let table = match op {
+        "cache" => CACHE_OPS,
+        "pref" => PREF_HINTS,
+        _ => return None,
+    };
+    table
+        .iter()
+        .find(|(x, _)| x.eq_ignore_ascii_case(name))
+        .map(|(_, code)| *code)
+}
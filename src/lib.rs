@@ -9,6 +9,9 @@
 //! - Coprocessor 0 register names
 //! - Fully-featured CLI
 //!
+//! Only 32-bit encodings are supported. MIPS16e and microMIPS code, started with `.set mips16` or
+//! `.set micromips`, is reported as an error instead of being assembled.
+//!
 //! ## Example
 //!
 //! ```rust
@@ -35,18 +38,213 @@ mod assembler;
 mod ast;
 mod disassembler;
 mod error;
+mod hint;
 mod parser;
+pub mod prelude;
+mod program;
+mod validate;
 
-pub use ast::Instruction;
-pub use error::ParserError;
+pub use ast::{
+    FloatCond, FloatRegister, ITypeOp, Immediate, Instruction, JTypeOp, RTypeOp, Register, Target,
+};
+pub use disassembler::disassemble_iter;
+pub use error::{DecodeError, Error, ParserError, ParserWarning};
+pub use program::{Program, RelocKind, Relocation, Stats};
+pub use strum::IntoEnumIterator;
+pub use validate::{Diagnostic, Lint, Severity};
 
 use std::collections::HashMap;
+use std::ops::Range;
+use strum_macros::{Display, EnumString};
+
+/// A MIPS instruction set architecture level.
+///
+/// Release 6 is only modelled as far as the instructions it adds, such as `addiupc`, and its removal
+/// of paired-single. Other instructions it removes or re-encodes are still accepted.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::Isa;
+///
+/// assert_eq!("mips32r2".parse::<Isa>(), Ok(Isa::Mips32r2));
+/// assert_eq!(Isa::Mips3.to_string(), "mips3");
+/// ```
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
+#[strum(serialize_all = "lowercase")]
+pub enum Isa {
+    Mips1,
+    Mips2,
+    Mips3,
+    Mips4,
+    Mips5,
+    Mips32,
+    Mips32r2,
+    Mips64,
+    Mips64r2,
+    Mips32r6,
+    Mips64r6,
+}
+
+impl Isa {
+    // Whether every instruction of `other` is also part of this ISA
+    pub(crate) fn includes(self, other: Isa) -> bool {
+        use Isa::*;
+        match other {
+            Mips1 => true,
+            Mips2 => self != Mips1,
+            Mips3 => matches!(self, Mips3 | Mips4 | Mips5 | Mips64 | Mips64r2 | Mips64r6),
+            // MIPS32 took the conditional moves from MIPS IV
+            Mips4 => !matches!(self, Mips1 | Mips2 | Mips3),
+            // Release 6 removed paired-single
+            Mips5 => matches!(self, Mips5 | Mips64 | Mips64r2),
+            Mips32 => self.is_mips32(),
+            Mips32r2 => matches!(self, Mips32r2 | Mips64r2 | Mips32r6 | Mips64r6),
+            Mips64 => matches!(self, Mips64 | Mips64r2 | Mips64r6),
+            Mips64r2 => matches!(self, Mips64r2 | Mips64r6),
+            Mips32r6 => matches!(self, Mips32r6 | Mips64r6),
+            Mips64r6 => self == Mips64r6,
+        }
+    }
+
+    // Whether the ISA includes the instructions introduced by MIPS32
+    pub(crate) fn is_mips32(self) -> bool {
+        matches!(
+            self,
+            Isa::Mips32
+                | Isa::Mips32r2
+                | Isa::Mips64
+                | Isa::Mips64r2
+                | Isa::Mips32r6
+                | Isa::Mips64r6
+        )
+    }
+}
+
+/// The byte order of words in a binary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// How far the mnemonic of each disassembled instruction is padded before its operands.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::MnemonicWidth;
+///
+/// assert_eq!("8".parse(), Ok(MnemonicWidth::Columns(8)));
+/// assert_eq!("tab".parse(), Ok(MnemonicWidth::Tab));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MnemonicWidth {
+    /// Pad the mnemonic with spaces so the operands start at this column, leaving at least one
+    /// space after longer mnemonics.
+    Columns(usize),
+    /// Separate the mnemonic from its operands with a tab.
+    Tab,
+}
+
+impl std::str::FromStr for MnemonicWidth {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("tab") {
+            Ok(MnemonicWidth::Tab)
+        } else {
+            s.parse().map(MnemonicWidth::Columns)
+        }
+    }
+}
+
+/// Limits and settings that control how input is parsed.
+///
+/// The defaults are generous enough for any hand-written or generated source, and exist so that
+/// untrusted input cannot force the parser into unbounded allocations.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::{Mipsasm, Options};
+///
+/// let mut mipsasm = Mipsasm::new();
+/// mipsasm.options(Options {
+///     max_line_length: 256,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    /// The maximum length of a single line of input in bytes. Defaults to 4096.
+    pub max_line_length: usize,
+    /// The maximum number of comma-separated operands on a single line. Defaults to 64.
+    pub max_operands: usize,
+    /// The maximum size of the assembled program in bytes, which `.space`, `.org` and `.align`
+    /// could otherwise grow without bound. Defaults to 64 MiB.
+    pub max_output_size: usize,
+    /// The instruction set to assemble for. Defaults to `None`, which accepts every supported
+    /// instruction and expands pseudo-instructions the way the R4300i requires.
+    pub isa: Option<Isa>,
+    /// Zero the fields that refer to undefined labels and print a warning instead of failing.
+    /// Defaults to false.
+    pub allow_unresolved: bool,
+    /// The byte used to fill gaps left by `.align`, `.org` and `.space` when the directive doesn't
+    /// give one. Defaults to 0.
+    pub fill_byte: u8,
+    /// Assemble position-independent code, enabling `.cpload` and `.cprestore`. This is also
+    /// enabled by an `.abicalls` directive. `%hi(_gp_disp)` and `%lo(_gp_disp)` are resolved
+    /// against the `_gp` label or symbol.
+    pub pic: bool,
+    /// The severity of each advisory lint of [`Program::validate`], or `None` to disable it. Lints
+    /// that aren't listed are warnings.
+    pub lints: HashMap<Lint, Option<Severity>>,
+    /// Report every warning as an error, including those printed while parsing, which then fail
+    /// assembly. Defaults to false.
+    pub warnings_as_errors: bool,
+    /// Insert a `nop` into the delay slot of every branch and jump, unless the next instruction is
+    /// already a `nop`, so that the instruction after a branch only runs when it isn't taken.
+    /// Instructions are never moved. `.set noreorder` turns this off for the code that follows,
+    /// and `.set reorder` turns it back on. Defaults to false.
+    pub fill_delay_slots: bool,
+    /// Skip `.set` options the assembler doesn't recognise with a warning instead of failing.
+    /// Options that GNU as accepts but that don't change the output, such as `volatile`, `nomove`
+    /// or `fp=64`, are always skipped silently. Defaults to false.
+    pub allow_unknown_set: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            max_line_length: 4096,
+            max_operands: 64,
+            max_output_size: 64 << 20,
+            isa: None,
+            allow_unresolved: false,
+            fill_byte: 0,
+            pic: false,
+            lints: HashMap::new(),
+            warnings_as_errors: false,
+            fill_delay_slots: false,
+            allow_unknown_set: false,
+        }
+    }
+}
 
 /// An instance of the assembler/disassembler
 pub struct Mipsasm<'a> {
     base_addr: u32,
     syms: HashMap<u32, &'a str>,
+    annotations: HashMap<u32, &'a str>,
     debug: bool,
+    split_syms: bool,
+    numeric_regs: bool,
+    annotate_calls: bool,
+    mnemonic_width: Option<MnemonicWidth>,
+    data_ranges: Vec<Range<u32>>,
+    options: Options,
 }
 
 impl<'a> Default for Mipsasm<'a> {
@@ -63,7 +261,14 @@ impl<'a> Mipsasm<'a> {
         Mipsasm {
             base_addr: 0,
             syms: HashMap::new(),
+            annotations: HashMap::new(),
             debug: false,
+            split_syms: false,
+            numeric_regs: false,
+            annotate_calls: false,
+            mnemonic_width: None,
+            data_ranges: vec![],
+            options: Options::default(),
         }
     }
 
@@ -98,6 +303,27 @@ impl<'a> Mipsasm<'a> {
         self
     }
 
+    /// Provides the disassembler with names for `syscall`, `break` and trap codes.
+    ///
+    /// Instructions whose code has a name are printed with the name as a trailing comment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    /// use std::collections::HashMap;
+    ///
+    /// let insts = Mipsasm::new()
+    ///     .annotations(HashMap::from_iter(vec![(0x4, "write")]))
+    ///     .debug()
+    ///     .disassemble(&[0x0000010c]);
+    /// assert_eq!(insts, vec!["syscall 0x4  # write"]);
+    /// ```
+    pub fn annotations(&mut self, annotations: HashMap<u32, &'a str>) -> &mut Mipsasm<'a> {
+        self.annotations = annotations;
+        self
+    }
+
     /// Set the debug flag for the assembler.
     ///
     /// When debug is set to true, the disassembler will print instructions with all extra whitespace stripped and will not emit labels.
@@ -116,8 +342,148 @@ impl<'a> Mipsasm<'a> {
         self
     }
 
+    /// Start a new function in the disassembly at every address that has a symbol.
+    ///
+    /// Without this, functions are only split after an unconditional jump that leaves the function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    /// use std::collections::HashMap;
+    ///
+    /// let insts = Mipsasm::new()
+    ///     .base(0x8000_0000)
+    ///     .symbols(HashMap::from_iter(vec![(0x8000_0004, "bar")]))
+    ///     .split_at_symbols()
+    ///     .disassemble(&[0x00a62020, 0x03e00008]);
+    /// assert_eq!(
+    ///     insts,
+    ///     vec!["func_80000000:", "add        $a0, $a1, $a2", "", "bar:", "jr         $ra"]
+    /// );
+    /// ```
+    pub fn split_at_symbols(&mut self) -> &mut Mipsasm<'a> {
+        self.split_syms = true;
+        self
+    }
+
+    /// Print registers by number in the disassembly, such as `$8` instead of `$t0` and `$f4` instead
+    /// of `$ft0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let insts = Mipsasm::new()
+    ///     .numeric_regs()
+    ///     .debug()
+    ///     .disassemble(&[0x01095020, 0x46062100]);
+    /// assert_eq!(insts, vec!["add $10, $8, $9", "add.s $f4, $f4, $f6"]);
+    /// ```
+    pub fn numeric_regs(&mut self) -> &mut Mipsasm<'a> {
+        self.numeric_regs = true;
+        self
+    }
+
+    /// Replace the targets of branches with the symbol at that address, as is always done for
+    /// jumps, and note the address of every target replaced by a symbol in a comment. Targets
+    /// without a symbol are printed as before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    /// use std::collections::HashMap;
+    ///
+    /// let insts = Mipsasm::new()
+    ///     .base(0x8000_0000)
+    ///     .symbols(HashMap::from([(0x8000_0008, "main")]))
+    ///     .annotate_calls()
+    ///     .debug()
+    ///     .disassemble(&[0x0c000002, 0x10800000, 0x03e00008]);
+    /// assert_eq!(
+    ///     insts,
+    ///     vec![
+    ///         "jal main  # 0x80000008",
+    ///         "beq $a0, $zero, main  # 0x80000008",
+    ///         "jr $ra"
+    ///     ]
+    /// );
+    /// ```
+    pub fn annotate_calls(&mut self) -> &mut Mipsasm<'a> {
+        self.annotate_calls = true;
+        self
+    }
+
+    /// Pad the mnemonics in the disassembly to this width instead of 11 columns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::{Mipsasm, MnemonicWidth};
+    ///
+    /// let insts = Mipsasm::new()
+    ///     .mnemonic_width(MnemonicWidth::Columns(8))
+    ///     .disassemble(&[0x01095020, 0x03e00008, 0x0000000c]);
+    /// assert_eq!(insts, vec!["func_00000000:", "add     $t2, $t0, $t1", "jr      $ra", "syscall"]);
+    ///
+    /// let insts = Mipsasm::new()
+    ///     .mnemonic_width(MnemonicWidth::Tab)
+    ///     .disassemble(&[0x03e00008]);
+    /// assert_eq!(insts, vec!["func_00000000:", "jr\t$ra"]);
+    /// ```
+    pub fn mnemonic_width(&mut self, width: MnemonicWidth) -> &mut Mipsasm<'a> {
+        self.mnemonic_width = Some(width);
+        self
+    }
+
+    /// Emit the words at these addresses as `.word` directives instead of disassembling them.
+    ///
+    /// Each word gets its own `.word 0x%08x` line, which assembles back to the same word, so data
+    /// survives a round trip through the disassembler. Can be called several times to mark several
+    /// ranges as data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let insts = Mipsasm::new()
+    ///     .base(0x8000_0000)
+    ///     .data_range(0x8000_0004..0x8000_0008)
+    ///     .debug()
+    ///     .disassemble(&[0x03e00008, 0x03e00008]);
+    /// assert_eq!(insts, vec!["jr $ra", ".word 0x03e00008"]);
+    /// ```
+    pub fn data_range(&mut self, range: Range<u32>) -> &mut Mipsasm<'a> {
+        self.data_ranges.push(range);
+        self
+    }
+
+    /// Set the parser options for the assembler.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::{Mipsasm, Options};
+    ///
+    /// let mut mipsasm = Mipsasm::new();
+    /// mipsasm.options(Options {
+    ///     max_operands: 8,
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn options(&mut self, options: Options) -> &mut Mipsasm<'a> {
+        self.options = options;
+        self
+    }
+
     /// Assembles a set of MIPS assembly instructions.
     ///
+    /// Malformed input is reported through the returned errors rather than a panic, so this is
+    /// safe to call on untrusted text.
+    ///
     /// # Examples
     ///
     /// ```
@@ -129,10 +495,83 @@ impl<'a> Mipsasm<'a> {
     /// ");
     /// ```
     pub fn assemble(&self, input: &str) -> Result<Vec<Instruction>, Vec<ParserError>> {
-        let mut parser = parser::Parser::new(input, self.base_addr, &self.syms);
+        self.assemble_program(input).map(Program::into_instructions)
+    }
+
+    /// Assembles a set of MIPS assembly instructions into a [`Program`].
+    ///
+    /// Alongside the instructions, the program records every position that references a label or
+    /// symbol by absolute address, so that it can be relocated at load time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    /// let mut mipsasm = Mipsasm::new();
+    /// let program = mipsasm.assemble_program("
+    ///   jal foo
+    ///   nop
+    /// foo:
+    ///   jr $ra
+    /// ").unwrap();
+    /// assert_eq!(program.relocations()[0].symbol, "foo");
+    /// ```
+    pub fn assemble_program(&self, input: &str) -> Result<Program, Vec<ParserError>> {
+        let mut parser = parser::Parser::new(input, self.base_addr, &self.syms, &self.options);
         let mut insts = parser.parse()?;
         assembler::assemble(&mut insts);
-        Ok(insts)
+        Ok(Program::new(
+            self.base_addr,
+            insts,
+            parser.labels(),
+            parser.relocs(),
+            parser.sources(),
+            parser.diagnostics(),
+            &self.options,
+        ))
+    }
+
+    /// Computes the size in bytes that the input assembles to, without resolving labels or encoding
+    /// any instructions.
+    ///
+    /// Pseudo-instructions are counted by the number of instructions they expand to, and data
+    /// directives and padding by the bytes they emit. Fails with the same errors as
+    /// [`Mipsasm::assemble`], except for undefined labels and other errors found while resolving
+    /// them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let size = Mipsasm::new().encoded_size("li $t0, 0x12345678\n.align 4\nb end\nnop\nend:").unwrap();
+    /// assert_eq!(size, 24);
+    /// ```
+    pub fn encoded_size(&self, input: &str) -> Result<usize, Vec<ParserError>> {
+        let mut parser = parser::Parser::new(input, self.base_addr, &self.syms, &self.options);
+        parser.size().map(|size| size as usize)
+    }
+
+    /// Assembles a single instruction into its word.
+    ///
+    /// Fails if the input assembles to anything other than exactly one word, such as a
+    /// pseudo-instruction that expands to several instructions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let word = Mipsasm::new().base(0x8000_0000).assemble_one("jal 0x80001000").unwrap();
+    /// assert_eq!(word, 0x0c000400);
+    /// assert!(Mipsasm::new().assemble_one("li $t0, 0x12345678").is_err());
+    /// ```
+    pub fn assemble_one(&self, input: &str) -> Result<u32, Error> {
+        let words = get_bytes(&self.assemble(input)?);
+        match words.as_slice() {
+            [word] => Ok(*word),
+            _ => Err(Error::NotOneWord(words.len())),
+        }
     }
 
     /// Disassembles a set of MIPS instructions.
@@ -146,52 +585,116 @@ impl<'a> Mipsasm<'a> {
     /// let instructions = mipsasm.disassemble(&[0x00850018]);
     /// ```
     pub fn disassemble(&self, input: &[u32]) -> Vec<String> {
-        let mut x = disassembler::disassemble(input.to_vec(), self.base_addr);
+        self.disassemble_checked(input).0
+    }
+
+    /// Disassembles a set of MIPS instructions, also returning every word that could not be decoded.
+    ///
+    /// Words that could not be decoded are still emitted as `.word` directives.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let (insts, errors) = Mipsasm::new()
+    ///     .base(0x8000_0000)
+    ///     .debug()
+    ///     .disassemble_checked(&[0x00a62020, 0x7c000000]);
+    /// assert_eq!(insts, vec!["add $a0, $a1, $a2", ".word 0x7c000000"]);
+    /// assert_eq!(errors[0].addr, 0x8000_0004);
+    /// ```
+    pub fn disassemble_checked(&self, input: &[u32]) -> (Vec<String>, Vec<DecodeError>) {
+        let (mut x, errors) =
+            disassembler::disassemble(input.to_vec(), self.base_addr, &self.data_ranges);
+        // Jump targets are needed as addresses after they have been replaced with symbol names
+        let targets = x.iter().map(|x| x.get_jump_target()).collect::<Vec<_>>();
+        let dests = (0..x.len())
+            .map(|i| x[i].branch_target(self.base_addr.wrapping_add(i as u32 * 4)))
+            .collect::<Vec<_>>();
         self.match_syms(&mut x);
 
-        if self.debug {
+        let out = if self.debug {
             x.iter()
-                .map(|x| format!("{:?}", x))
+                .zip(&dests)
+                .map(|(x, dest)| {
+                    format!(
+                        "{:?}{}",
+                        x.styled(self.numeric_regs),
+                        self.get_annotation(x, *dest)
+                    )
+                })
                 .collect::<Vec<String>>()
         } else {
             let mut out = vec![];
             let mut func_start = 0;
             let mut function_ended = false;
+            let mut func_addr = self.base_addr;
 
             out.push(format!("{}:", self.get_sym(self.base_addr)));
 
             for i in 0..x.len() {
+                let addr = self.base_addr.wrapping_add(i as u32 * 4);
+                if self.split_syms && addr != func_addr && self.syms.contains_key(&addr) {
+                    out.push(String::new());
+                    out.push(format!("{}:", self.get_sym(addr)));
+                    func_start = i * 4;
+                    func_addr = addr;
+                }
+
                 if function_ended {
-                    out.push(x[i].to_string());
+                    out.push(format!(
+                        "{}{}",
+                        x[i].styled(self.numeric_regs),
+                        self.get_annotation(&x[i], dests[i])
+                    ));
                     func_start = i * 4;
                     function_ended = false;
                     if i < x.len() - 1 {
+                        func_addr = addr.wrapping_add(4);
                         out.push(String::new());
-                        out.push(format!(
-                            "{}:",
-                            self.get_sym(self.base_addr + (i + 1) as u32 * 4)
-                        ));
+                        out.push(format!("{}:", self.get_sym(func_addr)));
                     }
                 } else {
                     if x[i].is_unconditional_jump()
-                        && (x[i].get_jump_target() < Some(func_start as u32 + self.base_addr)
-                            || x[i].get_jump_target() > Some(i as u32 * 4 + self.base_addr))
+                        && (targets[i] < Some(self.base_addr.wrapping_add(func_start as u32))
+                            || targets[i] > Some(addr))
                     {
                         function_ended = true;
                     }
 
-                    out.push(x[i].to_string());
+                    out.push(format!(
+                        "{}{}",
+                        x[i].styled(self.numeric_regs),
+                        self.get_annotation(&x[i], dests[i])
+                    ));
                 }
             }
 
             out
-        }
+        };
+
+        let out = match self.mnemonic_width {
+            Some(width) if !self.debug => {
+                out.iter().map(|x| ast::align_mnemonic(x, width)).collect()
+            }
+            _ => out,
+        };
+
+        (out, errors)
     }
 
     // Iterates over a vector of instructions and replaces any value with a defined symbol with the actual symbol string.
-    fn match_syms(&self, insts: &mut Vec<ast::Instruction>) {
-        for i in insts {
-            if let ast::Instruction::Jump {
+    fn match_syms(&self, insts: &mut [ast::Instruction]) {
+        for (n, i) in insts.iter_mut().enumerate() {
+            if self.annotate_calls && i.is_branch() {
+                let target = i.branch_target(self.base_addr.wrapping_add(n as u32 * 4));
+                if let Some(sym) = target.and_then(|target| self.syms.get(&target)) {
+                    if let ast::Instruction::Immediate { imm, .. } = i {
+                        *imm = ast::Immediate::Label(sym.to_string());
+                    }
+                }
+            } else if let ast::Instruction::Jump {
                 op,
                 target: ast::Target::Address(addr),
                 bytes,
@@ -208,6 +711,20 @@ impl<'a> Mipsasm<'a> {
         }
     }
 
+    // Returns the comment for an instruction: the name of its code, or the address of a target
+    // that was replaced with a symbol
+    fn get_annotation(&self, inst: &ast::Instruction, target: Option<u32>) -> String {
+        if let Some(name) = inst.get_code().and_then(|code| self.annotations.get(&code)) {
+            return format!("  # {}", name);
+        }
+        match target {
+            Some(target) if self.annotate_calls && self.syms.contains_key(&target) => {
+                format!("  # {:#010x}", target)
+            }
+            _ => String::new(),
+        }
+    }
+
     fn get_sym(&self, addr: u32) -> String {
         if let Some(sym) = self.syms.get(&addr) {
             sym.to_string()
@@ -233,3 +750,87 @@ impl<'a> Mipsasm<'a> {
 pub fn get_bytes(insts: &[Instruction]) -> Vec<u32> {
     insts.iter().flat_map(|x| x.get_bytes()).collect()
 }
+
+/// Assembles a single instruction at `addr` and writes it over the word at that address in a binary
+/// loaded at `base`.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::Endian;
+///
+/// let mut rom = vec![0; 8];
+/// mipsasm::patch(&mut rom, 0x8000_0004, 0x8000_0000, "jr $ra", Endian::Big).unwrap();
+/// assert_eq!(rom, vec![0, 0, 0, 0, 0x03, 0xe0, 0x00, 0x08]);
+/// assert!(mipsasm::patch(&mut rom, 0x8000_0008, 0x8000_0000, "nop", Endian::Big).is_err());
+/// ```
+pub fn patch(
+    bytes: &mut [u8],
+    addr: u32,
+    base: u32,
+    src: &str,
+    endian: Endian,
+) -> Result<(), Error> {
+    let offset = addr
+        .checked_sub(base)
+        .map(|offset| offset as usize)
+        .filter(|offset| offset.checked_add(4).is_some_and(|end| end <= bytes.len()))
+        .ok_or(Error::OutOfBounds {
+            addr,
+            base,
+            len: bytes.len(),
+        })?;
+    let word = Mipsasm::new().base(addr).assemble_one(src)?;
+    let word = match endian {
+        Endian::Big => word.to_be_bytes(),
+        Endian::Little => word.to_le_bytes(),
+    };
+    bytes[offset..offset + 4].copy_from_slice(&word);
+    Ok(())
+}
+
+/// Reads a symbol table in the format printed by `nm` or `objdump -t`.
+///
+/// Each line gives a symbol as a hexadecimal address followed by its type and name, such as
+/// `80001000 T main`, or by the flags, section and size that `objdump -t` prints before the name.
+/// 64-bit addresses are accepted if they are sign or zero extensions of a 32-bit address. Headers
+/// and undefined symbols, which have no address, are ignored.
+///
+/// Returns the symbols, and the line numbers of the lines that couldn't be read and were skipped.
+///
+/// # Examples
+///
+/// ```
+/// let (syms, skipped) = mipsasm::read_nm_symbols(
+///     "80001000 T main\n         U printf\nffffffff80002000 t helper\nmain\n",
+/// );
+/// assert_eq!(syms.get(&0x8000_1000), Some(&"main"));
+/// assert_eq!(syms.get(&0x8000_2000), Some(&"helper"));
+/// assert_eq!(skipped, vec![4]);
+/// ```
+pub fn read_nm_symbols(text: &str) -> (HashMap<u32, &str>, Vec<usize>) {
+    let mut syms = HashMap::new();
+    let mut skipped = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match fields.as_slice() {
+            [] | ["SYMBOL", "TABLE:"] => {}
+            [_, "file", "format", ..] => {}
+            [kind, _] if kind.len() == 1 => {}
+            _ if fields.contains(&"*UND*") => {}
+            [addr, .., name] if fields.len() >= 3 => {
+                let addr = u64::from_str_radix(addr, 16)
+                    .ok()
+                    .filter(|addr| matches!(addr >> 32, 0 | 0xffffffff));
+                match addr {
+                    Some(addr) => {
+                        syms.insert(addr as u32, *name);
+                    }
+                    None => skipped.push(i + 1),
+                }
+            }
+            _ => skipped.push(i + 1),
+        }
+    }
+    (syms, skipped)
+}
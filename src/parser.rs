@@ -1,6 +1,11 @@
+use crate::assembler;
 use crate::ast;
 use crate::error::{Line, ParserError, ParserWarning};
+use crate::hint;
+use crate::program::RelocKind;
+use crate::validate::{Diagnostic, Severity, Source};
 use crate::{error, warning};
+use crate::{Isa, Options};
 use indexmap::IndexMap;
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -53,43 +58,174 @@ macro_rules! inst {
 }
 
 // Regex to match anything after a comment
-static COMMENT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(/{2}|;).*").unwrap());
 static OFFSET_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r".+\s*\(").unwrap());
 static BASE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\(.*?\)").unwrap());
 static IMM_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\(.*\)").unwrap());
 
+// `.set` options that GNU as understands but that don't affect the output of this assembler.
+// `fp` and `gp` take a value, e.g. `.set fp=64`
+const IGNORED_SET_OPTIONS: &[&str] = &[
+    "at",
+    "autoextend",
+    "bopt",
+    "doublefloat",
+    "fp",
+    "gp",
+    "hardfloat",
+    "macro",
+    "move",
+    "noat",
+    "noautoextend",
+    "nobopt",
+    "nomacro",
+    "nomove",
+    "nosym32",
+    "novolatile",
+    "singlefloat",
+    "softfloat",
+    "sym32",
+    "volatile",
+];
+
+// The PIC symbol whose value is the distance from the instruction referencing it to `_gp`
+const GP_DISP: &str = "_gp_disp";
+
+// Settings that can be changed with `.set` and saved with `.set push`
+#[derive(Clone)]
+struct State {
+    isa: Option<Isa>,
+    // Cleared by `.set nohazards` to silence the hazard checks of `Program::validate`
+    hazards: bool,
+    // Whether a nop is inserted after every branch and jump. Only set with
+    // `Options::fill_delay_slots`, and suspended by `.set noreorder`
+    reorder: bool,
+    // Set by `.set mips16` or `.set micromips`. Compressed code can't be assembled, so everything
+    // but `.set` directives is skipped until it is turned off
+    compressed: bool,
+}
+
 pub struct Parser<'a> {
     input: Vec<&'a str>,
     insts: Vec<(usize, ast::Instruction)>,
+    offsets: Vec<u32>,
+    pc: u32,
     labels: IndexMap<String, usize>,
     local_labels: HashMap<String, (usize, String)>,
     local_labels_dropped: HashMap<String, Vec<(usize, String)>>,
     base_addr: u32,
     syms: &'a HashMap<u32, &'a str>,
+    options: &'a Options,
+    state: State,
+    saved_states: Vec<(usize, String, State)>,
+    pic: bool,
+    cprestore: Option<u32>,
+    relocs: Vec<(usize, RelocKind, String)>,
+    sources: Vec<Source>,
+    diagnostics: Vec<Diagnostic>,
     line_num: usize,
     errors: Vec<ParserError>,
+    // Set right after a nop has been inserted into a delay slot
+    slot_filled: bool,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(input: &'a str, base_addr: u32, syms: &'a HashMap<u32, &'a str>) -> Parser<'a> {
+    pub fn new(
+        input: &'a str,
+        base_addr: u32,
+        syms: &'a HashMap<u32, &'a str>,
+        options: &'a Options,
+    ) -> Parser<'a> {
         Parser {
             input: input.lines().collect(),
             insts: vec![],
+            offsets: vec![],
+            pc: 0,
             labels: IndexMap::new(),
             local_labels: HashMap::new(),
             local_labels_dropped: HashMap::new(),
             base_addr,
             syms,
+            options,
+            state: State {
+                isa: options.isa,
+                hazards: true,
+                reorder: options.fill_delay_slots,
+                compressed: false,
+            },
+            saved_states: vec![],
+            pic: options.pic,
+            cprestore: None,
+            relocs: vec![],
+            sources: vec![],
+            diagnostics: vec![],
             line_num: 0,
             errors: vec![],
+            slot_filled: false,
         }
     }
 
     pub fn parse(&mut self) -> Result<Vec<ast::Instruction>, Vec<ParserError>> {
+        // Labels past the point where layout stopped were never defined, so resolving would only add
+        // noise to the error that stopped it
+        if self.layout() {
+            self.adjust_labels()
+                .unwrap_or_else(|e| e.into_iter().for_each(|e| self.errors.push(e)));
+        }
+        if self.errors.is_empty() {
+            Ok(mem::take(&mut self.insts)
+                .into_iter()
+                .map(|(_, i)| i)
+                .collect())
+        } else {
+            Err(mem::take(&mut self.errors))
+        }
+    }
+
+    // Returns the size in bytes of the program without resolving any labels. Every instruction
+    // takes the same space whatever its labels resolve to, so this is the size `parse` produces
+    pub fn size(&mut self) -> Result<u32, Vec<ParserError>> {
+        self.layout();
+        if self.errors.is_empty() {
+            Ok(self.pc)
+        } else {
+            Err(mem::take(&mut self.errors))
+        }
+    }
+
+    // Parses every line and places its instructions and labels, leaving label references unresolved.
+    // Returns false if the program outgrew its limits and the remaining lines were skipped
+    fn layout(&mut self) -> bool {
+        let mut complete = true;
         for i in 0..self.input.len() {
             self.line_num += 1;
-            let l = COMMENT_RE.replace_all(self.input.get(i).unwrap().trim(), "");
-            self.scan_line(&l).unwrap_or_else(|e| self.errors.push(e));
+            let len = self.input.get(i).unwrap().len();
+            if len > self.options.max_line_length {
+                self.errors
+                    .push(error!(self, LineTooLong, len, self.options.max_line_length));
+                continue;
+            }
+            let l = strip_comment(self.input.get(i).unwrap().trim()).trim_end();
+            self.scan_line(l).unwrap_or_else(|e| self.errors.push(e));
+            // Padding directives can grow the program without bound, so stop before it is encoded
+            if self.pc as usize > self.options.max_output_size {
+                self.errors.push(error!(
+                    self,
+                    OutputTooLarge, self.pc as usize, self.options.max_output_size
+                ));
+                complete = false;
+                break;
+            }
+            // Addresses are the base plus an offset, so the program has to end inside the address
+            // space
+            if self.base_addr.checked_add(self.pc).is_none() {
+                self.errors.push(error!(self, AddressOverflow));
+                complete = false;
+                break;
+            }
+        }
+        for (line_num, option, _) in mem::take(&mut self.saved_states) {
+            self.errors
+                .push(error!(self, UnmatchedSet, line_num, option));
         }
         if !self.local_labels_dropped.is_empty() {
             let local_labels = mem::take(&mut self.local_labels);
@@ -104,16 +240,30 @@ impl<'a> Parser<'a> {
             }
             self.local_labels.clear()
         }
-        self.adjust_labels()
-            .unwrap_or_else(|e| e.into_iter().for_each(|e| self.errors.push(e)));
-        if self.errors.is_empty() {
-            Ok(mem::take(&mut self.insts)
-                .into_iter()
-                .map(|(_, i)| i)
-                .collect())
-        } else {
-            Err(mem::take(&mut self.errors))
-        }
+        complete
+    }
+
+    // Returns every label defined in the program with its address
+    pub fn labels(&self) -> Vec<(String, u32)> {
+        self.labels
+            .iter()
+            .map(|(name, idx)| (name.clone(), self.base_addr + self.offset_of(*idx)))
+            .collect()
+    }
+
+    // Returns the absolute references recorded while parsing, keyed by instruction index
+    pub fn relocs(&mut self) -> Vec<(usize, RelocKind, String)> {
+        mem::take(&mut self.relocs)
+    }
+
+    // Returns the line and settings each instruction was assembled with
+    pub fn sources(&mut self) -> Vec<Source> {
+        mem::take(&mut self.sources)
+    }
+
+    // Returns the non-fatal problems found while resolving labels
+    pub fn diagnostics(&mut self) -> Vec<Diagnostic> {
+        mem::take(&mut self.diagnostics)
     }
 
     fn scan_line(&mut self, line: &str) -> Result<(), ParserError> {
@@ -148,14 +298,351 @@ impl<'a> Parser<'a> {
                 }
             }
         } else if !line.is_empty() {
-            self.insts.push((self.line_num, self.parse_inst(line)?));
+            let (op, arg) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            if self.state.compressed && !op.eq_ignore_ascii_case(".set") {
+                return Ok(());
+            }
+            if op.eq_ignore_ascii_case(".align") {
+                self.parse_align(arg)?;
+            } else if op.eq_ignore_ascii_case(".org") {
+                self.parse_org(arg)?;
+            } else if op.eq_ignore_ascii_case(".space") {
+                self.parse_space(arg)?;
+            } else if op.eq_ignore_ascii_case(".set") {
+                self.parse_set(arg.trim())?;
+            } else if op.eq_ignore_ascii_case(".abicalls") {
+                self.pic = true;
+            } else if op.eq_ignore_ascii_case(".cpload") {
+                self.parse_cpload(arg)?;
+            } else if op.eq_ignore_ascii_case(".cprestore") {
+                self.parse_cprestore(arg)?;
+            } else if op.eq_ignore_ascii_case(".ascii") {
+                self.parse_ascii(arg, false)?;
+            } else if op.eq_ignore_ascii_case(".asciiz") {
+                self.parse_ascii(arg, true)?;
+            } else {
+                let inst = self.parse_inst(line)?;
+                // A nop written in a delay slot that has already been filled is redundant
+                if self.slot_filled
+                    && matches!(
+                        inst,
+                        ast::Instruction::Register {
+                            op: ast::RTypeOp::Nop,
+                            ..
+                        }
+                    )
+                {
+                    self.slot_filled = false;
+                    return Ok(());
+                }
+                self.push_inst(inst);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Appends an instruction to the program and advances the location counter past it. Branches
+    // and jumps are followed by a nop when delay slots are being filled
+    fn push_inst(&mut self, inst: ast::Instruction) {
+        let fill = self.state.reorder && inst.has_delay_slot();
+        self.offsets.push(self.pc);
+        self.sources.push(Source {
+            line: self.line_num,
+            isa: self.state.isa,
+            hazards: self.state.hazards,
+        });
+        self.pc += assembler::size(&inst);
+        self.insts.push((self.line_num, inst));
+        self.slot_filled = false;
+        if fill {
+            self.push_inst(inst!(
+                Reg,
+                "nop",
+                ast::Register::null(),
+                ast::Register::null(),
+                ast::Register::null()
+            ));
+            self.slot_filled = true;
+        }
+    }
+
+    // Returns the offset from the base address of the instruction at the given index
+    fn offset_of(&self, idx: usize) -> u32 {
+        self.offsets.get(idx).copied().unwrap_or(self.pc)
+    }
+
+    // .align [N[, fill]]
+    // Pads the program with the fill byte up to the next 2^N byte boundary.
+    // Without an operand, aligns to the next word boundary.
+    fn parse_align(&mut self, arg: &str) -> Result<(), ParserError> {
+        let args = split_operands(arg)
+            .into_iter()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<&str>>();
+        if args.len() > 2 {
+            return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
+        }
+
+        let align = match args.first() {
+            Some(x) => {
+                let n = self.parse_constant::<u16>(x)?;
+                if n > 16 {
+                    return Err(error!(self, InvalidImmediate, x));
+                }
+                1 << n
+            }
+            None => 4,
+        };
+        let fill = self.parse_fill(args.get(1).copied())?;
+        self.fill_to(self.pc.next_multiple_of(align), fill);
+        Ok(())
+    }
+
+    // .org address[, fill]
+    // Pads the program with the fill byte up to the given absolute address.
+    fn parse_org(&mut self, arg: &str) -> Result<(), ParserError> {
+        let args = split_operands(arg)
+            .into_iter()
+            .map(|x| x.trim())
+            .collect::<Vec<&str>>();
+        if args.len() > 2 {
+            return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
+        }
+
+        let target = args.first().unwrap();
+        let addr = self.parse_constant::<u32>(target)?;
+        let current = self
+            .base_addr
+            .checked_add(self.pc)
+            .ok_or_else(|| error!(self, AddressOverflow))?;
+        if addr < current {
+            return Err(error!(self, OrgBackwards, target, (addr, current)));
+        }
+        if !(addr - self.base_addr).is_multiple_of(4) {
+            return Err(error!(self, InvalidImmediate, target));
+        }
+
+        let fill = self.parse_fill(args.get(1).copied())?;
+        self.fill_to(addr - self.base_addr, fill);
+        Ok(())
+    }
+
+    // .space size[, fill]
+    // Reserves `size` bytes filled with the fill byte, rounded up to a whole number of words.
+    fn parse_space(&mut self, arg: &str) -> Result<(), ParserError> {
+        let args = split_operands(arg)
+            .into_iter()
+            .map(|x| x.trim())
+            .collect::<Vec<&str>>();
+        if args.len() > 2 {
+            return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
+        }
+
+        let size = self.parse_constant::<u32>(args.first().unwrap())?;
+        let fill = self.parse_fill(args.get(1).copied())?;
+        let end = size
+            .checked_next_multiple_of(4)
+            .and_then(|size| self.pc.checked_add(size))
+            .ok_or_else(|| error!(self, InvalidImmediate, args.first().unwrap()))?;
+        self.fill_to(end, fill);
+        Ok(())
+    }
+
+    // Parses the fill byte operand of a padding directive, falling back to the configured default
+    fn parse_fill(&mut self, arg: Option<&str>) -> Result<u32, ParserError> {
+        match arg {
+            Some(x) => {
+                let fill = self.parse_constant::<u16>(x)?;
+                if fill > 0xFF {
+                    return Err(error!(self, InvalidImmediate, x));
+                }
+                Ok(fill)
+            }
+            None => Ok(self.options.fill_byte as u32),
+        }
+    }
+
+    // Pads the program with the fill byte until the location counter reaches `offset`
+    fn fill_to(&mut self, offset: u32, fill: u32) {
+        if self.pc < offset {
+            self.push_inst(ast::Instruction::Fill {
+                bytes: fill * 0x01010101,
+                count: (offset - self.pc) / 4,
+            });
+        }
+    }
+
+    // .set option
+    // Changes an assembler setting for the rest of the file, or until it is restored with
+    // `.set pop`. `noreorder` and `reorder` suspend and resume `Options::fill_delay_slots`.
+    // `nohazards` and `hazards` turn the hazard checks of `Program::validate` off and on.
+    // `arch=isa` is the same as `.set isa`. MIPS16e and microMIPS aren't supported, so `mips16`
+    // and `micromips` are errors that skip the code up to `nomips16` or `nomicromips`. The
+    // options in `IGNORED_SET_OPTIONS` are accepted but don't change the output. Anything else is
+    // an error, or a warning with `Options::allow_unknown_set`.
+    fn parse_set(&mut self, option: &str) -> Result<(), ParserError> {
+        let lower = option.to_lowercase();
+        if let Some(arch) = lower.strip_prefix("arch=") {
+            return self.parse_set(arch);
+        }
+        if IGNORED_SET_OPTIONS.contains(&lower.split('=').next().unwrap()) {
+            return Ok(());
+        }
+
+        match lower.as_str() {
+            "push" => {
+                self.saved_states
+                    .push((self.line_num, option.to_string(), self.state.clone()))
+            }
+            "pop" => {
+                let (_, _, state) = self
+                    .saved_states
+                    .pop()
+                    .ok_or_else(|| error!(self, UnmatchedSet, self.line_num, option))?;
+                self.state = state;
+            }
+            "mips0" => self.state.isa = self.options.isa,
+            "hazards" => self.state.hazards = true,
+            "nohazards" => self.state.hazards = false,
+            "reorder" => self.state.reorder = self.options.fill_delay_slots,
+            "noreorder" => self.state.reorder = false,
+            "mips16" | "mips16e" | "micromips" => {
+                self.state.compressed = true;
+                return Err(error!(self, CompressedIsa, option));
+            }
+            "nomips16" | "nomicromips" => self.state.compressed = false,
+            isa => match isa.parse() {
+                Ok(isa) => self.state.isa = Some(isa),
+                Err(_) if self.options.allow_unknown_set => {
+                    self.warn(warning!(self, UnknownSetOption, option))
+                }
+                Err(_) => return Err(error!(self, InvalidSetOption, option)),
+            },
+        }
+        Ok(())
+    }
+
+    // .cpload reg
+    // Sets up $gp from the address of the function in `reg` (normally $t9) at the start of a PIC
+    // function. Ignored outside of PIC code.
+    fn parse_cpload(&mut self, arg: &str) -> Result<(), ParserError> {
+        let args = split_operands(arg);
+        if args.len() != 1 {
+            return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
+        }
+        let reg = args[0]
+            .parse()
+            .map_err(|ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e))?;
+        if !self.pic {
+            return Ok(());
         }
 
+        let gp = ast::Register::Gp;
+        self.push_inst(inst!(
+            Imm,
+            "lui",
+            ast::Register::null(),
+            gp,
+            ast::Immediate::Hi(GP_DISP.to_string())
+        ));
+        self.push_inst(inst!(
+            Imm,
+            "addiu",
+            gp,
+            gp,
+            ast::Immediate::Lo(GP_DISP.to_string())
+        ));
+        self.push_inst(inst!(Reg, "addu", gp, reg, gp));
+        Ok(())
+    }
+
+    // .cprestore offset
+    // Saves $gp to `offset($sp)` and remembers the offset it can be reloaded from after a call.
+    // Ignored outside of PIC code.
+    fn parse_cprestore(&mut self, arg: &str) -> Result<(), ParserError> {
+        let args = split_operands(arg);
+        if args.len() != 1 {
+            return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
+        }
+        let offset = self.parse_constant::<i16>(args[0])?;
+        if !self.pic {
+            return Ok(());
+        }
+
+        self.cprestore = Some(offset);
+        self.push_inst(inst!(
+            Imm,
+            "sw",
+            ast::Register::Sp,
+            ast::Register::Gp,
+            ast::Immediate::Short(offset as u16)
+        ));
+        Ok(())
+    }
+
+    // .ascii "string"[, "string"...]
+    // .asciiz "string"[, "string"...]
+    // Emits the bytes of each string, null-terminated for .asciiz, zero-padded to the next word.
+    fn parse_ascii(&mut self, arg: &str, terminate: bool) -> Result<(), ParserError> {
+        let mut bytes = vec![];
+        for string in split_operands(arg) {
+            bytes.extend(self.parse_string(string.trim())?);
+            if terminate {
+                bytes.push(0);
+            }
+        }
+        for word in bytes.chunks(4) {
+            let mut buf = [0; 4];
+            buf[..word.len()].copy_from_slice(word);
+            self.push_inst(ast::Instruction::Bytes {
+                bytes: u32::from_be_bytes(buf),
+            });
+        }
         Ok(())
     }
 
+    // Parses a double-quoted string literal, expanding escape sequences
+    fn parse_string(&self, string: &str) -> Result<Vec<u8>, ParserError> {
+        let inner = string
+            .strip_prefix('"')
+            .and_then(|x| x.strip_suffix('"'))
+            .ok_or_else(|| error!(self, InvalidString, string))?;
+
+        let mut bytes = vec![];
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c == '"' {
+                return Err(error!(self, InvalidString, string));
+            }
+            if c != '\\' {
+                let mut buf = [0; 4];
+                bytes.extend(c.encode_utf8(&mut buf).as_bytes());
+                continue;
+            }
+            bytes.push(match chars.next() {
+                Some('n') => b'\n',
+                Some('t') => b'\t',
+                Some('r') => b'\r',
+                Some('0') => 0,
+                Some('\\') => b'\\',
+                Some('"') => b'"',
+                Some('x') => {
+                    let hex = chars.as_str().get(..2).unwrap_or_default();
+                    let byte = u8::from_str_radix(hex, 16)
+                        .map_err(|_| error!(self, InvalidString, string))?;
+                    chars.nth(1);
+                    byte
+                }
+                _ => return Err(error!(self, InvalidString, string)),
+            });
+        }
+        Ok(bytes)
+    }
+
     fn parse_label(&self, label: String) -> Result<String, ParserError> {
-        if label.chars().next().unwrap().is_numeric() {
+        if label.chars().next().is_none_or(char::is_numeric) {
             return Err(error!(self, InvalidLabel, label));
         }
         if self.labels.contains_key(&label) {
@@ -169,8 +656,8 @@ impl<'a> Parser<'a> {
         Ok(label)
     }
 
-    fn parse_inst(&self, line: &str) -> Result<ast::Instruction, ParserError> {
-        let (op, arg) = match line.split_once(' ') {
+    fn parse_inst(&mut self, line: &str) -> Result<ast::Instruction, ParserError> {
+        let (op, arg) = match line.split_once(char::is_whitespace) {
             Some((op, arg)) => (op, arg),
             None => (line, ""),
         };
@@ -179,7 +666,16 @@ impl<'a> Parser<'a> {
             return Err(error!(self, InvalidInstruction));
         }
 
-        let args = arg.split(',').collect::<Vec<&str>>();
+        let args = split_operands(arg);
+        if args.len() > self.options.max_operands {
+            return Err(error!(
+                self,
+                TooManyOperands,
+                arg,
+                self.options.max_operands,
+                args.len()
+            ));
+        }
 
         match op.to_lowercase().trim() {
             // -----------------------------------------------------------------
@@ -187,40 +683,39 @@ impl<'a> Parser<'a> {
             // ------6----------5---------5-------------------16----------------
             //  Format:  op rt, offset(base)
             "cache" | "lb" | "lbu" | "ld" | "ldl" | "ldr" | "lh" | "lhu" | "ll" | "lld" | "lw"
-            | "lwl" | "lwr" | "lwu" | "sb" | "sc" | "scd" | "sd" | "sdl" | "sdr" | "sh" | "sw"
-            | "swl" | "swr" => {
+            | "lwl" | "lwr" | "lwu" | "pref" | "sb" | "sc" | "scd" | "sd" | "sdl" | "sdr"
+            | "sh" | "sw" | "swl" | "swr" => {
                 if args.len() != 2 {
                     return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
                 }
-                let rt = if op.to_lowercase().trim() == "cache" {
-                    ast::Register::try_from(
-                        self.parse_immediate::<u16>(args.first().unwrap())?.as_u32(),
-                    )
-                    .unwrap()
+                let op = op.trim().to_lowercase();
+                let rt = if op == "cache" || op == "pref" {
+                    let code = match hint::code(&op, args[0].trim()) {
+                        Some(code) => code,
+                        None => self.parse_constant::<u16>(args[0])?,
+                    };
+                    ast::Register::try_from(code)
+                        .map_err(|_| error!(self, InvalidImmediate, args[0]))?
                 } else {
-                    args.first().unwrap().parse().unwrap()
+                    args[0]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?
                 };
-                let x = args.get(1).unwrap();
-                let base = BASE_RE
-                    .find_iter(x)
-                    .last()
-                    .unwrap()
-                    .as_str()
-                    .replace(&['(', ')'][..], "")
-                    .trim()
-                    .parse()
-                    .unwrap();
-                if let Some(x) = OFFSET_RE.find(x) {
-                    Ok(inst!(
-                        Imm,
-                        op,
-                        base,
-                        rt,
-                        self.parse_immediate::<i16>(&x.as_str()[..x.as_str().len() - 1])?
-                    ))
-                } else {
-                    Ok(inst!(Imm, op, base, rt, self.parse_immediate::<i16>("0")?))
+                let (base, offset) = self.parse_offset_base(args.get(1).unwrap())?;
+                Ok(inst!(Imm, op, base, rt, offset))
+            }
+            // -----------------------------------------------------------------
+            // |  REGIMM   |  base   |  11111  |            offset             |
+            // ------6----------5---------5-------------------16----------------
+            //  Format:  op offset(base)
+            "synci" => {
+                if args.len() != 1 {
+                    return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
                 }
+                let (base, offset) = self.parse_offset_base(args[0])?;
+                Ok(inst!(Imm, op, base, ast::Register::Ra, offset))
             }
             // -----------------------------------------------------------------
             // |    op     |   rs    |   rt    |          immediate            |
@@ -284,6 +779,26 @@ impl<'a> Parser<'a> {
                     self.parse_immediate::<i16>(imm)?
                 ))
             }
+            // -----------------------------------------------------------------
+            // |  PCREL    |   rs    |op |               offset                |
+            // ------6----------5-------2-------------------19------------------
+            //  Format:  op rs, target
+            "addiupc" | "lwpc" => {
+                if args.len() != 2 {
+                    return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
+                }
+                let rs = args[0]
+                    .parse()
+                    .map_err(|ast::RegParseError::RegParseError(e)| {
+                        error!(self, InvalidRegister, e)
+                    })?;
+                let imm = self.parse_branch_target(args[1])?;
+                // The offset counts words, so the target must be word aligned
+                if !imm.is_label() && imm.as_u32() % 4 != 0 {
+                    return Err(error!(self, InvalidImmediate, args[1]));
+                }
+                Ok(inst!(Imm, op, rs, ast::Register::null(), imm))
+            }
             "bgez" | "bgezal" | "bgezall" | "bgezl" | "bltz" | "bltzal" | "bltzall" | "bltzl"
             | "beqz" | "bnez" | "beqzl" | "bnezl" | "bgtz" | "bgtzl" | "blez" | "blezl" => {
                 if args.len() != 2 {
@@ -292,16 +807,16 @@ impl<'a> Parser<'a> {
                 if !self.insts.is_empty() {
                     let (_, last) = self.insts.last().unwrap();
                     if last.has_delay_slot() {
-                        eprintln!("{}", warning!(self, InvalidInstructionInDelaySlot))
+                        self.warn(warning!(self, InvalidInstructionInDelaySlot))
                     }
                 }
                 let rs = args.first().unwrap().parse().map_err(
                     |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
                 )?;
                 let offset = args.get(1).unwrap();
-                let imm = self.parse_immediate::<u32>(offset)?;
+                let imm = self.parse_branch_target(offset)?;
                 if !imm.is_label() && imm.as_u32() % 4 != 0 {
-                    eprintln!("{}", warning!(self, UnalignedBranch, offset.to_string()));
+                    self.warn(warning!(self, UnalignedBranch, offset.to_string()));
                 }
                 Ok(inst!(Imm, op, rs, ast::Register::null(), imm))
             }
@@ -318,7 +833,7 @@ impl<'a> Parser<'a> {
                 if !self.insts.is_empty() {
                     let (_, last) = self.insts.last().unwrap();
                     if last.has_delay_slot() {
-                        eprintln!("{}", warning!(self, InvalidInstructionInDelaySlot))
+                        self.warn(warning!(self, InvalidInstructionInDelaySlot))
                     }
                 }
                 let rs = args.first().unwrap().parse().map_err(
@@ -328,9 +843,9 @@ impl<'a> Parser<'a> {
                     |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
                 )?;
                 let offset = args.get(2).unwrap();
-                let imm = self.parse_immediate::<u32>(offset)?;
+                let imm = self.parse_branch_target(offset)?;
                 if !imm.is_label() && imm.as_u32() % 4 != 0 {
-                    eprintln!("{}", warning!(self, UnalignedBranch, offset.to_string()));
+                    self.warn(warning!(self, UnalignedBranch, offset.to_string()));
                 }
                 Ok(inst!(Imm, op, rs, rt, imm))
             }
@@ -345,21 +860,80 @@ impl<'a> Parser<'a> {
                 if !self.insts.is_empty() {
                     let (_, last) = self.insts.last().unwrap();
                     if last.has_delay_slot() {
-                        eprintln!("{}", warning!(self, InvalidInstructionInDelaySlot))
+                        self.warn(warning!(self, InvalidInstructionInDelaySlot))
                     }
                 }
                 let target_str = args.first().unwrap().trim();
                 let target = self.parse_target(target_str)?;
+                self.add_reloc(RelocKind::Jump26, target_str);
                 if !target.is_label() && target.as_u32() % 4 != 0 {
-                    eprintln!("{}", warning!(self, UnalignedJump, target_str.to_string()));
+                    self.warn(warning!(self, UnalignedJump, target_str.to_string()));
                 }
                 Ok(inst!(Jump, op, target))
             }
             // -----------------------------------------------------------------
             // |  SPECIAL  |      0000 0000 0000 000     |  stype  |    op     |
             // ------6-------------------15-------------------5---------6-------
-            //  Format:  op          (stype = 0 implied)
-            "nop" | "sync" => Ok(inst!(
+            //  Format:  op[ stype]
+            // -----------------------------------------------------------------
+            // |   COP0    |CO|             code             |       op        |
+            // ------6------1----------------19-----------------------6---------
+            //  Format:  op[ code]
+            "sync" | "wait" => {
+                if args.len() != 1 {
+                    return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
+                }
+                let max = if op.eq_ignore_ascii_case("sync") {
+                    0x1F
+                } else {
+                    0x7FFFF
+                };
+                let code = if args[0].is_empty() {
+                    0
+                } else {
+                    self.parse_constant::<u32>(args[0])?
+                };
+                if code > max {
+                    return Err(error!(self, InvalidImmediate, args[0]));
+                }
+                Ok(inst!(
+                    Reg,
+                    op,
+                    ast::Register::null(),
+                    ast::Register::null(),
+                    ast::Register::null(),
+                    code
+                ))
+            }
+            // -----------------------------------------------------------------
+            // |   COPz    |CO|                   cofun                        |
+            // ------6------1-----------------------25--------------------------
+            //  Format:  op cofun
+            "cop0" | "cop1" | "cop2" => {
+                if args.len() != 1 {
+                    return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
+                }
+                if args[0].is_empty() {
+                    return Err(error!(self, InvalidOperandCount, arg, 1, 0));
+                }
+                let cofun = self.parse_constant::<u32>(args[0])?;
+                if cofun > 0x1FFFFFF {
+                    return Err(error!(self, InvalidImmediate, args[0]));
+                }
+                Ok(inst!(
+                    Reg,
+                    op,
+                    ast::Register::null(),
+                    ast::Register::null(),
+                    ast::Register::null(),
+                    cofun
+                ))
+            }
+            // -----------------------------------------------------------------
+            // |  SPECIAL  |      0000 0000 0000 000     |  00000  |    op     |
+            // ------6-------------------15-------------------5---------6-------
+            //  Format:  op
+            "ehb" | "nop" | "pause" | "ssnop" => Ok(inst!(
                 Reg,
                 op,
                 ast::Register::null(),
@@ -386,6 +960,17 @@ impl<'a> Parser<'a> {
                 let rt = args.get(2).unwrap().parse().map_err(
                     |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
                 )?;
+                // `mul` is only a single instruction from MIPS32 onwards
+                if op.eq_ignore_ascii_case("mul") && !self.state.isa.is_some_and(Isa::is_mips32) {
+                    return Ok(ast::Instruction::Register {
+                        op: ast::RTypeOp::MulMflo,
+                        rs,
+                        rt,
+                        rd,
+                        sa: 0,
+                        bytes: vec![],
+                    });
+                }
                 Ok(inst!(Reg, op, rs, rt, rd))
             }
             // -----------------------------------------------------------------
@@ -403,18 +988,23 @@ impl<'a> Parser<'a> {
                     |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
                 )?;
                 let sa = args.get(2).unwrap().trim();
+                let shift = if sa.ends_with('`') || !sa.contains("0x") {
+                    sa.trim_end_matches('`').parse::<i32>()
+                } else {
+                    i32::from_str_radix(&sa.replace("0x", ""), 16)
+                };
                 Ok(inst!(
                     Reg,
                     op,
                     ast::Register::null(),
                     rt,
                     rd,
-                    if sa.ends_with('`') || !sa.contains("0x") {
-                        sa.trim_end_matches('`').parse::<i32>().unwrap() as u32
-                    } else {
-                        let sa = sa.replace("0x", "");
-                        i32::from_str_radix(&sa, 16).unwrap() as u32
-                    }
+                    // The shift amount is a 5-bit field, dsll32 and friends add 32 to it themselves
+                    shift
+                        .ok()
+                        .filter(|shift| (0..32).contains(shift))
+                        .ok_or_else(|| error!(self, InvalidImmediate, sa))?
+                        as u32
                 ))
             }
             // -----------------------------------------------------------------
@@ -445,9 +1035,9 @@ impl<'a> Parser<'a> {
                     return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
                 }
                 let code = if args.first().unwrap().is_empty() {
-                    ast::Immediate::Short(0)
+                    0
                 } else if !args.first().unwrap().is_empty() {
-                    self.parse_immediate::<u16>(args.first().unwrap().trim())?
+                    self.parse_constant::<u16>(args.first().unwrap().trim())?
                 } else {
                     return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
                 };
@@ -458,56 +1048,95 @@ impl<'a> Parser<'a> {
                     ast::Register::null(),
                     ast::Register::null(),
                     ast::Register::null(),
-                    code.as_u32()
+                    code
                 ))
             }
             // -----------------------------------------------------------------
             // |  SPECIAL  |   rs    |   rt    |   0000 0000 00    |    op     |
             // ------6----------5---------5--------------10--------------6------
             //  Format:  op rs, rt
-            "dmult" | "dmultu" | "mult" | "multu" | "teq" | "tge" | "tgeu" | "tlt" | "tltu"
-            | "tne" => {
-                match args.len() {
-                   3 => {
-                       let rd = args[0].parse().map_err(
-                            |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
-                        )?;
-                       let rs = args[1].parse().map_err(
-                            |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
-                        )?;
-                       let rt = args[2].parse().map_err(
-                            |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
-                        )?;
-                       Ok(inst!(Reg, op, rs, rt, rd))
-                   }
-                   2 => {
-                       let rs = args[0].parse().map_err(
-                            |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
-                        )?;
-                       let rt = args[1].parse().map_err(
-                            |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
-                        )?;
-                       Ok(inst!(Reg, op, rs, rt, ast::Register::null()))
-                   }
-                   _ => {
-                       return Err(error!(self, InvalidOperandCount, arg, 0, args.len()));
-                   }
-               }
+            "dmult" | "dmultu" | "mult" | "multu" => match args.len() {
+                3 => {
+                    let rd = args[0]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    let rs = args[1]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    let rt = args[2]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    Ok(inst!(Reg, op, rs, rt, rd))
+                }
+                2 => {
+                    let rs = args[0]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    let rt = args[1]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    Ok(inst!(Reg, op, rs, rt, ast::Register::null()))
+                }
+                _ => Err(error!(self, InvalidOperandCount, arg, 0, args.len())),
+            },
+            // -----------------------------------------------------------------
+            // |  SPECIAL  |   rs    |   rt    |       code        |    op     |
+            // ------6----------5---------5--------------10--------------6------
+            //  Format:  op rs, rt[, code]
+            "teq" | "tge" | "tgeu" | "tlt" | "tltu" | "tne" => {
+                if args.len() != 2 && args.len() != 3 {
+                    return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
+                }
+                let rs = args[0]
+                    .parse()
+                    .map_err(|ast::RegParseError::RegParseError(e)| {
+                        error!(self, InvalidRegister, e)
+                    })?;
+                let rt = args[1]
+                    .parse()
+                    .map_err(|ast::RegParseError::RegParseError(e)| {
+                        error!(self, InvalidRegister, e)
+                    })?;
+                let code = match args.get(2) {
+                    Some(code) => {
+                        let code = self.parse_constant::<u16>(code)?;
+                        if code > 0x3FF {
+                            return Err(error!(self, InvalidImmediate, args[2]));
+                        }
+                        code
+                    }
+                    None => 0,
+                };
+                Ok(inst!(Reg, op, rs, rt, ast::Register::null(), code))
             }
             // -----------------------------------------------------------------
             // |  SPECIAL  |   rs    |  00000  |   rd    |  00000  |    op     |
             // ------6----------5---------5---------5---------5----------6------
             //  Format:  op rd, rs
-            "jalr" => {
+            "jalr" | "jalr.hb" => {
                 if args.len() != 2 && args.len() != 1 {
                     return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
                 }
                 if !self.insts.is_empty() {
                     let (_, last) = self.insts.last().unwrap();
                     if last.has_delay_slot() {
-                        eprintln!("{}", warning!(self, InvalidInstructionInDelaySlot))
+                        self.warn(warning!(self, InvalidInstructionInDelaySlot))
                     }
                 }
+                // PIC calls clobber $gp, so it has to have been saved with `.cprestore`
+                if self.pic && self.cprestore.is_none() {
+                    self.warn(warning!(self, MissingCprestore))
+                }
                 let rs = args.first().unwrap().parse().map_err(
                     |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
                 )?;
@@ -539,14 +1168,14 @@ impl<'a> Parser<'a> {
                 if !self.insts.is_empty() {
                     let (_, last) = self.insts.last().unwrap();
                     if last.has_delay_slot() {
-                        eprintln!("{}", warning!(self, InvalidInstructionInDelaySlot))
+                        self.warn(warning!(self, InvalidInstructionInDelaySlot))
                     }
                 }
 
                 let offset = args.first().unwrap();
-                let imm = self.parse_immediate::<u32>(offset)?;
+                let imm = self.parse_branch_target(offset)?;
                 if !imm.is_label() && imm.as_u32() % 4 != 0 {
-                    eprintln!("{}", warning!(self, UnalignedBranch, offset.to_string()));
+                    self.warn(warning!(self, UnalignedBranch, offset.to_string()));
                 }
                 Ok(inst!(
                     Imm,
@@ -564,7 +1193,7 @@ impl<'a> Parser<'a> {
                     |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
                 )?;
                 let imm = self.parse_immediate::<i64>(args.get(1).unwrap())?;
-                if imm.as_u64() > 0xFFFFFFFF {
+                if imm.is_label() || imm.as_u64() > 0xFFFFFFFF {
                     return Err(error!(
                         self,
                         InvalidImmediate,
@@ -581,7 +1210,7 @@ impl<'a> Parser<'a> {
                     |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
                 )?;
                 let imm = self.parse_immediate::<i64>(args.get(1).unwrap())?;
-                if imm.as_u64() > 0xFFFFFFFF {
+                if imm.is_label() || imm.as_u64() > 0xFFFFFFFF {
                     return Err(error!(
                         self,
                         InvalidImmediate,
@@ -609,30 +1238,52 @@ impl<'a> Parser<'a> {
                     Ok(inst!(Reg, op, rs, rt, rd))
                 }
             }
-            "div" | "divu" => {
-                if args.len() != 2 {
-                    return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
+            "div" | "divu" => match args.len() {
+                3 => {
+                    let rd = args[0]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    let rs = args[1]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    let rt = args[2]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    Ok(inst!(Reg, op, rs, rt, rd))
                 }
-                let rs = args[0].parse().map_err(
-                    |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
-                )?;
-                let rt = args[1].parse().map_err(
-                    |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
-                )?;
-                Ok(inst!(Reg, op, rs, rt, ast::Register::null()))
-            }
+                2 => {
+                    let rs = args[0]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    let rt = args[1]
+                        .parse()
+                        .map_err(|ast::RegParseError::RegParseError(e)| {
+                            error!(self, InvalidRegister, e)
+                        })?;
+                    Ok(inst!(Reg, op, rs, rt, ast::Register::null()))
+                }
+                _ => Err(error!(self, InvalidOperandCount, arg, 2, args.len())),
+            },
             // -----------------------------------------------------------------
             // |  SPECIAL  |   rs    |     0000 0000 0000 000      |    op     |
             // ------6----------5------------------15--------------------6------
             //  Format:  op rs
-            "jr" | "mthi" | "mtlo" => {
+            "jr" | "jr.hb" | "mthi" | "mtlo" => {
                 if args.len() != 1 {
                     return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
                 }
-                if op.to_lowercase().trim() == "jr" && !self.insts.is_empty() {
+                if op.to_lowercase().starts_with("jr") && !self.insts.is_empty() {
                     let (_, last) = self.insts.last().unwrap();
                     if last.has_delay_slot() {
-                        eprintln!("{}", warning!(self, InvalidInstructionInDelaySlot))
+                        self.warn(warning!(self, InvalidInstructionInDelaySlot))
                     }
                 }
                 let rs = args.first().unwrap().parse().map_err(
@@ -692,13 +1343,13 @@ impl<'a> Parser<'a> {
                 if !self.insts.is_empty() {
                     let (_, last) = self.insts.last().unwrap();
                     if last.has_delay_slot() {
-                        eprintln!("{}", warning!(self, InvalidInstructionInDelaySlot))
+                        self.warn(warning!(self, InvalidInstructionInDelaySlot))
                     }
                 }
                 let offset = args.first().unwrap();
-                let imm = self.parse_immediate::<u32>(offset)?;
+                let imm = self.parse_branch_target(offset)?;
                 if !imm.is_label() && imm.as_u32() % 4 != 0 {
-                    eprintln!("{}", warning!(self, UnalignedBranch, offset.to_string()));
+                    self.warn(warning!(self, UnalignedBranch, offset.to_string()));
                 }
 
                 Ok(inst!(
@@ -744,10 +1395,27 @@ impl<'a> Parser<'a> {
                 Ok(inst!(Reg, op, ast::Register::null(), rt, rd.into()))
             }
             // -----------------------------------------------------------------
+            // |   COP0    |   op    |   rt    |   rd    |    0000 0000 000    |
+            // ------6----------5---------5---------5--------------11-----------
+            //  Format:  op rd, rt
+            "rdpgpr" | "wrpgpr" => {
+                if args.len() != 2 {
+                    return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
+                }
+                let rd = args.first().unwrap().parse().map_err(
+                    |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
+                )?;
+                let rt = args.get(1).unwrap().parse().map_err(
+                    |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
+                )?;
+
+                Ok(inst!(Reg, op, ast::Register::null(), rt, rd))
+            }
+            // -----------------------------------------------------------------
             // |   COPz    |CO|      0000 0000 0000 0000 000       |    op     |
             // ------6------1-------------------19-----------------------6------
             //  Format:  op
-            "eret" | "tlbp" | "tlbr" | "tlbwi" | "tlbwr" => Ok(inst!(
+            "deret" | "eret" | "tlbp" | "tlbr" | "tlbwi" | "tlbwr" => Ok(inst!(
                 Reg,
                 op,
                 ast::Register::null(),
@@ -755,6 +1423,29 @@ impl<'a> Parser<'a> {
                 ast::Register::null()
             )),
             // -----------------------------------------------------------------
+            // |   COP0    |  MFMC0  |   rt    |  01100  | 000000 |sc|  00000  |
+            // ------6----------5---------5---------5--------6-----1-----5------
+            //  Format:  op [rt]
+            "di" | "ei" => {
+                if args.len() != 1 {
+                    return Err(error!(self, InvalidOperandCount, arg, 1, args.len()));
+                }
+                let rt = if args.first().unwrap().trim().is_empty() {
+                    ast::Register::null()
+                } else {
+                    args.first().unwrap().parse().map_err(
+                        |ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e),
+                    )?
+                };
+                Ok(inst!(
+                    Reg,
+                    op,
+                    ast::Register::null(),
+                    rt,
+                    ast::Register::null()
+                ))
+            }
+            // -----------------------------------------------------------------
             // |    op     |   base  |   ft    |            offset             |
             // ------6----------5---------5-------------------16----------------
             //  Format:  op ft, offset(base)
@@ -769,45 +1460,25 @@ impl<'a> Parser<'a> {
                     .map_err(|ast::RegParseError::RegParseError(e)| {
                         error!(self, InvalidRegister, e)
                     })?;
-                let x = args.get(1).unwrap();
-                let base = BASE_RE
-                    .find_iter(x)
-                    .last()
-                    .unwrap()
-                    .as_str()
-                    .replace(&['(', ')'][..], "")
-                    .trim()
-                    .parse()
-                    .map_err(|ast::RegParseError::RegParseError(e)| {
-                        error!(self, InvalidRegister, e)
-                    })?;
-                if let Some(x) = OFFSET_RE.find(x) {
-                    Ok(inst!(
-                        Imm,
-                        op,
-                        base,
-                        ast::Register::from(ft),
-                        self.parse_immediate::<i16>(&x.as_str().replace('(', ""))?
-                    ))
-                } else {
-                    Ok(inst!(
-                        Imm,
-                        op,
-                        base,
-                        ast::Register::from(ft),
-                        self.parse_immediate::<i16>("0")?
-                    ))
-                }
+                let (base, offset) = self.parse_offset_base(args.get(1).unwrap())?;
+                Ok(inst!(Imm, op, base, ast::Register::from(ft), offset))
             }
             ".word" => Ok(ast::Instruction::Bytes {
-                bytes: self.parse_immediate::<u32>(arg)?.as_u32(),
+                bytes: self.parse_constant::<u32>(arg)?,
             }),
-            _ => match &op.to_lowercase()[..op.len() - 2] {
+            _ => match op
+                .rsplit_once('.')
+                .unwrap_or_default()
+                .0
+                .to_lowercase()
+                .as_str()
+            {
                 // -----------------------------------------------------------------
                 // |   COP1    |   fmt   |   ft    |   fs    |   fd    |    op     |
                 // ------6----------5---------5---------5---------5----------6------
                 //  Format:  op.fmt fd, fs, ft
-                "add" | "sub" | "mul" | "div" => {
+                "add" | "cvt.ps" | "div" | "mul" | "pll" | "plu" | "pul" | "puu" | "sub" => {
+                    self.check_format(op)?;
                     if args.len() != 3 {
                         return Err(error!(self, InvalidOperandCount, arg, 3, args.len()));
                     }
@@ -840,6 +1511,7 @@ impl<'a> Parser<'a> {
                 "abs" | "ceil.l" | "ceil.w" | "cvt.d" | "cvt.l" | "cvt.s" | "cvt.w" | "floor.l"
                 | "floor.w" | "mov" | "neg" | "round.l" | "round.w" | "sqrt" | "trunc.l"
                 | "trunc.w" => {
+                    self.check_format(op)?;
                     if args.len() != 2 {
                         return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
                     }
@@ -867,7 +1539,9 @@ impl<'a> Parser<'a> {
                     // |   COP1    |   fmt   |   ft    |   fs    | 000 |00 |11 | cond  |
                     // ------6----------5---------5---------5-------3----2---2-----4----
                     //  Format:  C.cond.fmt fs, ft
+                    //  `c.cond.ps` compares both lanes, setting condition codes 0 and 1
                     if e.starts_with("c.") {
+                        self.check_format(op)?;
                         if args.len() != 2 {
                             return Err(error!(self, InvalidOperandCount, arg, 2, args.len()));
                         }
@@ -884,7 +1558,7 @@ impl<'a> Parser<'a> {
 
                         return Ok(inst!(
                             Reg,
-                            format!("C.{}", op.chars().last().unwrap()),
+                            format!("c.{}", op.rsplit_once('.').unwrap().1),
                             ast::Register::from(fs),
                             ast::Register::from(ft),
                             ast::Register::null(),
@@ -899,9 +1573,47 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // Parses an operand of the form `offset(base)`, where the offset is optional
+    fn parse_offset_base(
+        &mut self,
+        arg: &str,
+    ) -> Result<(ast::Register, ast::Immediate), ParserError> {
+        let base = BASE_RE
+            .find_iter(arg)
+            .last()
+            .ok_or_else(|| error!(self, InvalidImmediate, arg.trim()))?
+            .as_str()
+            .replace(&['(', ')'][..], "")
+            .trim()
+            .parse()
+            .map_err(|ast::RegParseError::RegParseError(e)| error!(self, InvalidRegister, e))?;
+        let offset = match OFFSET_RE.find(arg) {
+            Some(x) if !x.as_str()[..x.as_str().len() - 1].trim().is_empty() => {
+                self.parse_immediate::<i16>(&x.as_str()[..x.as_str().len() - 1])?
+            }
+            _ => self.parse_immediate::<i16>("0")?,
+        };
+        Ok((base, offset))
+    }
+
+    // Checks that a floating point instruction has a valid format suffix
+    fn check_format(&self, op: &str) -> Result<(), ParserError> {
+        let (base, fmt) = op.rsplit_once('.').unwrap_or_default();
+        let valid = if base.to_lowercase().starts_with("c.") {
+            matches!(fmt.to_lowercase().as_str(), "s" | "d" | "ps")
+        } else {
+            op.parse::<ast::RTypeOp>().is_ok()
+        };
+        if !valid {
+            return Err(error!(self, InvalidFormat, base, fmt));
+        }
+        Ok(())
+    }
+
     // Convert each label to an absolute immediate or address
     fn adjust_labels(&mut self) -> Result<(), Vec<ParserError>> {
         let mut errors = Vec::new();
+        let mut diagnostics = Vec::new();
         'a: for i in 0..self.insts.len() {
             if let ast::Instruction::Immediate {
                 op,
@@ -911,8 +1623,19 @@ impl<'a> Parser<'a> {
                 ..
             } = &self.insts[i].1
             {
-                let lbl_addr = self.labels.get(lbl.as_str()).unwrap();
-                let imm = ast::Immediate::Short((*lbl_addr as isize - (i + 1) as isize) as u16);
+                let imm = match self.labels.get(lbl.as_str()) {
+                    Some(&lbl_idx) => {
+                        self.target_offset(*op, i, self.offset_of(lbl_idx), &mut diagnostics)
+                    }
+                    None if self.options.allow_unresolved => {
+                        self.warn_unresolved(i, lbl, &mut errors);
+                        ast::Immediate::Short(0)
+                    }
+                    None => {
+                        errors.push(error!(self, UndefinedLabel, self.insts[i].0, lbl));
+                        continue;
+                    }
+                };
                 self.insts[i].1 = ast::Instruction::Immediate {
                     op: *op,
                     rs: *rs,
@@ -939,7 +1662,7 @@ impl<'a> Parser<'a> {
                     if i < lower || i > upper {
                         continue;
                     }
-                    let imm = ast::Immediate::Short((*addr as isize - (i + 1) as isize) as u16);
+                    let imm = self.target_offset(*op, i, self.offset_of(*addr), &mut diagnostics);
                     self.insts[i].1 = ast::Instruction::Immediate {
                         op: *op,
                         rs: *rs,
@@ -959,27 +1682,68 @@ impl<'a> Parser<'a> {
                 ..
             } = &self.insts[i].1
             {
-                if !op.to_string().starts_with('b') {
+                let pc_relative = matches!(op, ast::ITypeOp::Addiupc | ast::ITypeOp::Lwpc);
+                if !pc_relative && !self.insts[i].1.is_branch() {
                     continue;
                 }
 
-                // Make sure the address is within the bounds of the program
-                if *addr < self.base_addr || *addr > self.base_addr + self.insts.len() as u32 * 4 {
+                // Make sure the address is within the bounds of the program. PC-relative
+                // instructions may also refer to data outside of it
+                if !pc_relative && (*addr < self.base_addr || *addr > self.base_addr + self.pc) {
                     errors.push(error!(
                         self,
                         BranchOutOfBounds,
                         self.insts[i].0,
                         self.input.get(self.insts[i].0 - 1).unwrap().to_string(),
-                        (self.base_addr, self.base_addr + self.insts.len() as u32 * 4)
+                        (self.base_addr, self.base_addr + self.pc)
                     ));
                 }
 
-                let offset = *addr as isize - (i + 1) as isize;
+                let imm =
+                    self.target_offset(*op, i, addr.wrapping_sub(self.base_addr), &mut diagnostics);
                 self.insts[i].1 = ast::Instruction::Immediate {
                     op: *op,
                     rs: *rs,
                     rt: *rt,
-                    imm: ast::Immediate::Short(((offset / 4) as i16) as u16),
+                    imm,
+                    bytes: vec![],
+                };
+            } else if let ast::Instruction::Immediate {
+                op,
+                rs,
+                rt,
+                imm: imm @ (ast::Immediate::Hi(lbl) | ast::Immediate::Lo(lbl)),
+                ..
+            } = &self.insts[i].1
+            {
+                let addr = if lbl == GP_DISP {
+                    // The distance from the `lui` of the `%hi`/`%lo` pair to `_gp`
+                    let lui = match imm {
+                        ast::Immediate::Hi(_) => self.offset_of(i),
+                        _ => self.offset_of(i).wrapping_sub(4),
+                    };
+                    self.label_addr("_gp")
+                        .map(|gp| gp.wrapping_sub(self.base_addr.wrapping_add(lui)))
+                } else {
+                    self.label_addr(lbl)
+                };
+                let imm = match (addr, imm) {
+                    (Some(addr), ast::Immediate::Hi(_)) => hi(addr),
+                    (Some(addr), _) => lo(addr),
+                    (None, _) if self.options.allow_unresolved => {
+                        self.warn_unresolved(i, lbl, &mut errors);
+                        0
+                    }
+                    (None, _) => {
+                        errors.push(error!(self, UndefinedLabel, self.insts[i].0, lbl));
+                        continue;
+                    }
+                };
+                self.insts[i].1 = ast::Instruction::Immediate {
+                    op: *op,
+                    rs: *rs,
+                    rt: *rt,
+                    imm: ast::Immediate::new(imm),
                     bytes: vec![],
                 };
             } else if let ast::Instruction::Jump {
@@ -988,26 +1752,150 @@ impl<'a> Parser<'a> {
                 ..
             } = &self.insts[i].1
             {
-                let lbl_addr = self.labels.get(lbl.as_str());
-                if lbl_addr.is_none() {
-                    errors.push(error!(self, UndefinedLabel, self.insts[i].0, lbl));
-                    continue;
-                }
+                let target = match self.labels.get(lbl.as_str()) {
+                    Some(&lbl_idx) => self.base_addr + self.offset_of(lbl_idx),
+                    None if self.options.allow_unresolved => {
+                        self.warn_unresolved(i, lbl, &mut errors);
+                        0
+                    }
+                    None => {
+                        errors.push(error!(self, UndefinedLabel, self.insts[i].0, lbl));
+                        continue;
+                    }
+                };
                 self.insts[i].1 = ast::Instruction::Jump {
                     op: *op,
-                    target: ast::Target::Address(self.base_addr + *lbl_addr.unwrap() as u32 * 4),
+                    target: ast::Target::Address(target),
                     bytes: vec![],
                 };
             }
         }
 
+        self.diagnostics.append(&mut diagnostics);
         if !errors.is_empty() {
             return Err(errors);
         }
         Ok(())
     }
 
-    fn parse_immediate<T>(&self, imm: &str) -> Result<ast::Immediate, ParserError>
+    // Returns the address of a label, or of a symbol if no label has the name
+    fn label_addr(&self, name: &str) -> Option<u32> {
+        match self.labels.get(name) {
+            Some(&idx) => Some(self.base_addr.wrapping_add(self.offset_of(idx))),
+            None => self
+                .syms
+                .iter()
+                .find(|(_, v)| **v == name)
+                .map(|(addr, _)| *addr),
+        }
+    }
+
+    // Prints a warning, or records it as an error if warnings are treated as errors
+    fn warn(&mut self, warning: ParserWarning) {
+        if self.options.warnings_as_errors {
+            self.errors.push(ParserError::DeniedWarning { warning });
+        } else {
+            eprintln!("{}", warning);
+        }
+    }
+
+    // Reports a reference to an undefined label that was left zeroed
+    fn warn_unresolved(&self, i: usize, label: &str, errors: &mut Vec<ParserError>) {
+        let warning = warning!(
+            self,
+            UnresolvedReference,
+            self.insts[i].0,
+            label,
+            self.offset_of(i)
+        );
+        if self.options.warnings_as_errors {
+            errors.push(ParserError::DeniedWarning { warning });
+        } else {
+            eprintln!("{}", warning);
+        }
+    }
+
+    // Calculates the offset in instructions from the delay slot of the branch at index `i` to `target`
+    fn branch_offset(
+        &self,
+        i: usize,
+        target: u32,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> ast::Immediate {
+        let offset = (target as i64 - (self.offset_of(i) as i64 + 4)) / 4;
+        if i16::try_from(offset).is_err() {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                line: self.insts[i].0,
+                message: format!(
+                    "branch target is {} instructions away, but a branch can only reach {} to {}",
+                    offset,
+                    i16::MIN,
+                    i16::MAX
+                ),
+            });
+        }
+        ast::Immediate::Short(offset as i16 as u16)
+    }
+
+    // PC-relative instructions count words from their own address in a signed 19-bit field
+    fn pc_offset(
+        &self,
+        i: usize,
+        target: u32,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> ast::Immediate {
+        let offset = (target as i64 - self.offset_of(i) as i64) / 4;
+        if !(-(1 << 18)..1 << 18).contains(&offset) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                line: self.insts[i].0,
+                message: format!(
+                    "target is {} words away, but a PC-relative instruction can only reach {} to {}",
+                    offset,
+                    -(1 << 18),
+                    (1 << 18) - 1
+                ),
+            });
+        }
+        ast::Immediate::Int(offset as u32 & 0x7FFFF)
+    }
+
+    // The encoded distance from instruction `i` to `target`, an offset into the program
+    fn target_offset(
+        &self,
+        op: ast::ITypeOp,
+        i: usize,
+        target: u32,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> ast::Immediate {
+        match op {
+            ast::ITypeOp::Addiupc | ast::ITypeOp::Lwpc => self.pc_offset(i, target, diagnostics),
+            _ => self.branch_offset(i, target, diagnostics),
+        }
+    }
+
+    // Parses the target of a branch, which may be a label that has not been defined yet
+    fn parse_branch_target(&mut self, target: &str) -> Result<ast::Immediate, ParserError> {
+        let target = target.trim();
+        if target.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '.') {
+            return Ok(ast::Immediate::Label(target.to_string()));
+        }
+        self.parse_immediate::<u32>(target)
+    }
+
+    // Parses an operand that must be a plain number, such as a shift amount or a trap code
+    fn parse_constant<T>(&mut self, imm: &str) -> Result<u32, ParserError>
+    where
+        T: num::PrimInt + std::str::FromStr,
+    {
+        match self.parse_immediate::<T>(imm)? {
+            x if x.is_label() => Err(error!(self, InvalidImmediate, imm.trim())),
+            x => Ok(x.as_u32()),
+        }
+    }
+
+    fn parse_immediate<T>(&mut self, imm: &str) -> Result<ast::Immediate, ParserError>
     where
         T: num::PrimInt + std::str::FromStr,
     {
@@ -1021,16 +1909,40 @@ impl<'a> Parser<'a> {
             return Ok(ast::Immediate::Label(imm.to_string()));
         }
 
-        if let Some(x) = IMM_RE.find(imm) {
-            let x = self.parse_target(&x.as_str().replace(&['(', ')'][..], ""))?;
-            match &imm[..3] {
+        if let Some(m) = IMM_RE.find(imm) {
+            let target = m.as_str().replace(&['(', ')'][..], "");
+            let x = self.parse_target(&target)?;
+            match &imm[..m.start()] {
                 "%hi" => {
-                    return Ok(ast::Immediate::new(
-                        ((x.as_u32() + (x.as_u32() & 0x8000) * 2) >> 16) as u16,
-                    ))
+                    self.add_reloc(RelocKind::Hi16, &target);
+                    // Labels are resolved once every address is known
+                    return Ok(match x {
+                        ast::Target::Label(lbl) => ast::Immediate::Hi(lbl),
+                        x => ast::Immediate::new(hi(x.as_u32())),
+                    });
                 }
-                "%lo" => return Ok(ast::Immediate::new((x.as_u32() & 0xffff) as u16)),
-                _ => todo!(),
+                "%lo" => {
+                    self.add_reloc(RelocKind::Lo16, &target);
+                    return Ok(match x {
+                        ast::Target::Label(lbl) => ast::Immediate::Lo(lbl),
+                        x => ast::Immediate::new(lo(x.as_u32())),
+                    });
+                }
+                op if op.starts_with('%') => return Err(error!(self, UnknownOperator, op)),
+                _ => return Err(error!(self, InvalidImmediate, imm)),
+            }
+        }
+
+        // Older assemblers write hex with a trailing `h`, e.g. `0FFh`. The leading digit keeps it
+        // from being mistaken for a label
+        if let Some(hex) = imm.strip_suffix(['h', 'H']) {
+            let digits = hex.strip_prefix('-').unwrap_or(hex);
+            if digits.starts_with(|c: char| c.is_ascii_digit())
+                && digits.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                return Ok(ast::Immediate::new::<T>(
+                    T::from_str_radix(hex, 16).map_err(|_| error!(self, InvalidImmediate, imm))?,
+                ));
             }
         }
 
@@ -1047,6 +1959,15 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // Records a reference to a label or symbol for the instruction currently being parsed
+    fn add_reloc(&mut self, kind: RelocKind, target: &str) {
+        // `_gp_disp` is relative to the instruction, so it never needs relocating
+        if !target.starts_with(|c: char| c.is_ascii_digit()) && target != GP_DISP {
+            self.relocs
+                .push((self.insts.len(), kind, target.to_string()));
+        }
+    }
+
     fn parse_target(&self, target: &str) -> Result<ast::Target, ParserError> {
         if let Some(x) = self.syms.iter().find(|(_, v)| **v == target) {
             return Ok(ast::Target::Address(*x.0));
@@ -1058,7 +1979,7 @@ impl<'a> Parser<'a> {
                 u32::from_str_radix(&target, 16)
                     .map_err(|_| error!(self, InvalidTargetAddress, target))?,
             ))
-        } else if target[0..1].parse::<u32>().is_ok() {
+        } else if target.starts_with(|c: char| c.is_ascii_digit()) {
             Ok(ast::Target::Address(
                 target
                     .parse::<u32>()
@@ -1105,3 +2026,51 @@ impl<'a> Parser<'a> {
         }
     }
 }
+
+// Splits the operands of a line on every comma that is not inside a quoted string
+fn split_operands(arg: &str) -> Vec<&str> {
+    let mut operands = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in arg.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                operands.push(&arg[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    operands.push(&arg[start..]);
+    operands
+}
+
+// Removes a trailing `//` or `;` comment that is not inside a quoted string
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &line[..i],
+            '/' if !quoted && line[i + 1..].starts_with('/') => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// The upper half of an address, adjusted for the sign extension of the matching `%lo`
+fn hi(addr: u32) -> u16 {
+    (addr.wrapping_add((addr & 0x8000) * 2) >> 16) as u16
+}
+
+fn lo(addr: u32) -> u16 {
+    (addr & 0xffff) as u16
+}
@@ -0,0 +1,27 @@
+//! Everything needed to assemble, disassemble and inspect instructions, for glob importing.
+//!
+//! # Examples
+//!
+//! ```
+//! use mipsasm::prelude::*;
+//!
+//! let insts = Mipsasm::new().assemble("addiu $a0, $a1, 0x10").unwrap();
+//! assert!(matches!(
+//!     insts[0],
+//!     Instruction::Immediate {
+//!         op: ITypeOp::Addiu,
+//!         rs: Register::A1,
+//!         rt: Register::A0,
+//!         imm: Immediate::Short(0x10),
+//!         ..
+//!     }
+//! ));
+//!
+//! let (addr, inst) = disassemble_iter(get_bytes(&insts), 0x8000_0000).next().unwrap();
+//! assert_eq!((addr, inst.unwrap().to_string().as_str()), (0x8000_0000, "addiu      $a0, $a1, 0x10"));
+//! ```
+
+pub use crate::{
+    disassemble_iter, get_bytes, DecodeError, FloatCond, FloatRegister, ITypeOp, Immediate,
+    Instruction, Isa, JTypeOp, Mipsasm, Options, ParserError, Program, RTypeOp, Register, Target,
+};
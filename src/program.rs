@@ -0,0 +1,545 @@
+use crate::ast::{ITypeOp, Immediate, Instruction, Target};
+use crate::disassembler;
+use crate::validate::{self, Diagnostic, Lint, Severity, Source, Word};
+use crate::Options;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The kind of absolute reference a [`Relocation`] patches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelocKind {
+    /// The 26-bit target field of a `j`/`jal`.
+    Jump26,
+    /// The 16-bit immediate of a `%hi(symbol)` reference.
+    Hi16,
+    /// The 16-bit immediate of a `%lo(symbol)` reference.
+    Lo16,
+}
+
+/// A position in an assembled program that references an absolute address by name.
+///
+/// A runtime loader can use these to fix up the program after moving it to its real load address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// The offset in bytes from the start of the program to the referencing instruction.
+    pub offset: u32,
+    /// How the address is encoded in the instruction.
+    pub kind: RelocKind,
+    /// The label or symbol the instruction refers to.
+    pub symbol: String,
+}
+
+/// A breakdown of the words of an assembled program by what they were assembled from.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::Mipsasm;
+///
+/// let program = Mipsasm::new().assemble_program("
+///     li $t0, 0x12345678
+///     jr $ra
+///     nop
+///     .align 5
+///     .word 0x1234
+/// ").unwrap();
+/// let stats = program.stats();
+/// assert_eq!(
+///     (stats.instructions, stats.expansions, stats.data, stats.padding),
+///     (1, 3, 1, 4)
+/// );
+/// assert_eq!(stats.total(), 9);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Words assembled from machine instructions.
+    pub instructions: usize,
+    /// Words assembled from pseudo-instructions, such as `li` and `nop`, and from instructions that
+    /// expand to several words, such as `div` with three operands.
+    pub expansions: usize,
+    /// Words of data from `.word`, `.ascii` and the other data directives.
+    pub data: usize,
+    /// Words of padding from `.align`, `.org` and `.space`.
+    pub padding: usize,
+}
+
+impl Stats {
+    /// The total number of words in the program.
+    pub fn total(&self) -> usize {
+        self.instructions + self.expansions + self.data + self.padding
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = |words: usize| match self.total() {
+            0 => 0.0,
+            total => words as f64 * 100.0 / total as f64,
+        };
+        for (name, words) in [
+            ("instructions", self.instructions),
+            ("pseudo-instruction expansions", self.expansions),
+            ("data", self.data),
+            ("padding", self.padding),
+        ] {
+            writeln!(
+                f,
+                "{:<30} {:>8} words {:>6.1}%",
+                name,
+                words,
+                percent(words)
+            )?;
+        }
+        write!(
+            f,
+            "{:<30} {:>8} words ({} bytes)",
+            "total",
+            self.total(),
+            self.total() * 4
+        )
+    }
+}
+
+/// The result of assembling a program.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::{Mipsasm, RelocKind};
+///
+/// let program = Mipsasm::new().base(0x8000_0000).assemble_program("
+/// foo:
+///     j foo
+///     nop
+/// ").unwrap();
+/// assert_eq!(program.words(), vec![0x08000000, 0x00000000]);
+/// assert_eq!(program.relocations()[0].offset, 0);
+/// assert_eq!(program.relocations()[0].kind, RelocKind::Jump26);
+/// ```
+#[derive(Debug)]
+pub struct Program {
+    base: u32,
+    insts: Vec<Instruction>,
+    labels: Vec<(String, u32)>,
+    relocs: Vec<Relocation>,
+    sources: Vec<Source>,
+    diagnostics: Vec<Diagnostic>,
+    lints: HashMap<Lint, Option<Severity>>,
+    warnings_as_errors: bool,
+}
+
+impl Program {
+    pub(crate) fn new(
+        base: u32,
+        insts: Vec<Instruction>,
+        mut labels: Vec<(String, u32)>,
+        relocs: Vec<(usize, RelocKind, String)>,
+        sources: Vec<Source>,
+        diagnostics: Vec<Diagnostic>,
+        options: &Options,
+    ) -> Self {
+        labels.sort_by_key(|(_, addr)| *addr);
+
+        let mut offsets = Vec::with_capacity(insts.len());
+        let mut offset = 0;
+        for inst in &insts {
+            offsets.push(offset);
+            offset += inst.get_bytes().len() as u32 * 4;
+        }
+        let relocs = relocs
+            .into_iter()
+            .map(|(i, kind, symbol)| Relocation {
+                offset: offsets[i],
+                kind,
+                symbol,
+            })
+            .collect();
+        Program {
+            base,
+            insts,
+            labels,
+            relocs,
+            sources,
+            diagnostics,
+            lints: options.lints.clone(),
+            warnings_as_errors: options.warnings_as_errors,
+        }
+    }
+
+    /// The address the program was assembled at.
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    /// The assembled instructions.
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.insts
+    }
+
+    /// Consumes the program and returns the assembled instructions.
+    pub fn into_instructions(self) -> Vec<Instruction> {
+        self.insts
+    }
+
+    /// The size of the assembled program in bytes.
+    ///
+    /// Use [`Mipsasm::encoded_size`](crate::Mipsasm::encoded_size) to compute this without
+    /// assembling the program.
+    pub fn encoded_size(&self) -> usize {
+        self.insts
+            .iter()
+            .map(|inst| inst.get_bytes().len() * 4)
+            .sum()
+    }
+
+    /// The assembled words of the program.
+    pub fn words(&self) -> Vec<u32> {
+        crate::get_bytes(&self.insts)
+    }
+
+    /// Every label defined in the program with its address, sorted by address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let program = Mipsasm::new().base(0x8000_0000).assemble_program("
+    /// foo:
+    ///     nop
+    /// bar:
+    ///     jr $ra
+    /// ").unwrap();
+    /// let labels: Vec<(&str, u32)> = program.labels().collect();
+    /// assert_eq!(labels, vec![("foo", 0x8000_0000), ("bar", 0x8000_0004)]);
+    /// ```
+    pub fn labels(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.labels
+            .iter()
+            .map(|(name, addr)| (name.as_str(), *addr))
+    }
+
+    /// The address of the given label, if it is defined.
+    pub fn label(&self, name: &str) -> Option<u32> {
+        self.labels
+            .iter()
+            .find(|(label, _)| label == name)
+            .map(|(_, addr)| *addr)
+    }
+
+    /// Every instruction that references a label or symbol by absolute address, in program order.
+    ///
+    /// References to plain numeric addresses are not included, since there is no symbol for a loader
+    /// to resolve them against.
+    pub fn relocations(&self) -> &[Relocation] {
+        &self.relocs
+    }
+
+    /// Maps every word of the program back to the source line that produced it, as CSV.
+    ///
+    /// Each row holds the address and value of a word, the line it was assembled from, and the
+    /// labels defined at its address separated by `;`. Words emitted by directives such as `.word`
+    /// and `.align` are included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let program = Mipsasm::new().base(0x8000_0000).assemble_program("main:
+    ///     li $t0, 0x12345678
+    /// data:
+    ///     .word 0xdeadbeef
+    /// ").unwrap();
+    /// assert_eq!(
+    ///     program.map(),
+    ///     "address,word,line,label
+    /// 0x80000000,0x3c081234,2,main
+    /// 0x80000004,0x35085678,2,
+    /// 0x80000008,0xdeadbeef,4,data
+    /// "
+    /// );
+    /// ```
+    pub fn map(&self) -> String {
+        let mut map = String::from("address,word,line,label\n");
+        let mut addr = self.base;
+        for (inst, source) in self.insts.iter().zip(&self.sources) {
+            for word in inst.get_bytes() {
+                let labels = self
+                    .labels
+                    .iter()
+                    .filter(|(_, a)| *a == addr)
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>();
+                map += &format!(
+                    "0x{:08x},0x{:08x},{},{}\n",
+                    addr,
+                    word,
+                    source.line,
+                    labels.join(";")
+                );
+                addr = addr.wrapping_add(4);
+            }
+        }
+        map
+    }
+
+    /// Counts the words of the program that came from machine instructions, pseudo-instruction
+    /// expansions, data directives and padding.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        let mut addr = self.base;
+        for inst in &self.insts {
+            let words = inst.get_bytes();
+            match inst {
+                Instruction::Fill { .. } => stats.padding += words.len(),
+                Instruction::Bytes { .. } => stats.data += words.len(),
+                // An instruction that assembles to a single word of itself is a machine instruction
+                _ if words.len() == 1
+                    && disassembler::decode(words[0], addr)
+                        .is_ok_and(|decoded| decoded.mnemonic() == inst.mnemonic()) =>
+                {
+                    stats.instructions += 1
+                }
+                _ => stats.expansions += words.len(),
+            }
+            addr = addr.wrapping_add(words.len() as u32 * 4);
+        }
+        stats
+    }
+
+    /// Runs every semantic check over the program and returns the problems found, sorted by line.
+    ///
+    /// None of these problems stop the program from being assembled. The checks are:
+    /// - branches whose target is out of range
+    /// - instructions that write to `$zero`
+    /// - branches and jumps in a delay slot
+    /// - 64-bit floating point values in odd-numbered registers
+    /// - instructions that aren't part of the ISA selected with [`Options::isa`](crate::Options::isa)
+    ///   or `.set`
+    /// - registers read before a load into them has completed, for ISAs before MIPS II, including at
+    ///   the target of a branch with a load in its delay slot
+    /// - writes to HI and LO within two instructions of `mfhi` or `mflo`, for ISAs before MIPS IV
+    ///
+    /// When no ISA is selected, the hazard checks assume the oldest one. They can be turned off for
+    /// part of the source with `.set nohazards` and back on with `.set hazards`.
+    ///
+    /// Every check except the ISA and branch range checks is a [`Lint`], reported as a warning unless
+    /// changed with [`Options::lints`]. [`Options::warnings_as_errors`] reports every problem as an
+    /// error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::{Mipsasm, Severity};
+    ///
+    /// let program = Mipsasm::new().assemble_program("
+    ///     addiu $zero, $a0, 1
+    ///     jr $ra
+    ///     nop
+    /// ").unwrap();
+    /// let diagnostics = program.validate();
+    /// assert_eq!(diagnostics.len(), 1);
+    /// assert_eq!(diagnostics[0].severity, Severity::Warning);
+    /// assert_eq!(diagnostics[0].line, 2);
+    /// ```
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut words = vec![];
+        let mut addr = self.base;
+        for (inst, source) in self.insts.iter().zip(&self.sources) {
+            for word in inst.get_bytes() {
+                words.push(Word {
+                    addr,
+                    line: source.line,
+                    isa: source.isa,
+                    hazards: source.hazards,
+                    inst: match inst {
+                        Instruction::Bytes { .. } | Instruction::Fill { .. } => None,
+                        _ => validate::decode(word, addr),
+                    },
+                });
+                addr = addr.wrapping_add(4);
+            }
+        }
+
+        let mut diagnostics = self.diagnostics.clone();
+        diagnostics.extend(validate::validate(&words, &self.lints));
+        if self.warnings_as_errors {
+            for diagnostic in &mut diagnostics {
+                diagnostic.severity = Severity::Error;
+            }
+        }
+        diagnostics.sort_by_key(|d| d.line);
+        diagnostics
+    }
+}
+
+impl Program {
+    // Decodes a word of `inst` back into source form, naming branch and jump targets after their
+    // labels. Data and padding stay data
+    fn render(&self, inst: &Instruction, word: u32, addr: u32) -> String {
+        if let Instruction::Bytes { .. } | Instruction::Fill { .. } = inst {
+            return Instruction::Bytes { bytes: word }.to_string();
+        }
+        let name = |target: u32| match self.labels.iter().find(|(_, a)| *a == target) {
+            Some((name, _)) => name.clone(),
+            None => format!("0x{:08x}", target),
+        };
+        let inst = match disassembler::decode(word, addr) {
+            Ok(mut inst) if inst.is_branch() => {
+                let label = name(inst.branch_target(addr).unwrap());
+                if let Instruction::Immediate { imm, .. } = &mut inst {
+                    *imm = Immediate::Label(label);
+                }
+                inst
+            }
+            Ok(Instruction::Immediate {
+                op: op @ (ITypeOp::Addiupc | ITypeOp::Lwpc),
+                rs,
+                rt,
+                imm,
+                bytes,
+            }) => {
+                let offset = ((imm.as_u32() << 13) as i32 >> 13) * 4;
+                Instruction::Immediate {
+                    op,
+                    rs,
+                    rt,
+                    imm: Immediate::Label(name(addr.wrapping_add(offset as u32))),
+                    bytes,
+                }
+            }
+            Ok(Instruction::Jump { op, target, bytes }) => Instruction::Jump {
+                op,
+                target: Target::Label(name(target.as_u32())),
+                bytes,
+            },
+            Ok(inst) => inst,
+            Err(_) => Instruction::Bytes { bytes: word },
+        };
+        inst.to_string()
+    }
+}
+
+/// Prints the program as a listing that can be assembled again.
+///
+/// Labels are printed on their own lines, and every word is printed on an indented line followed by
+/// its address. Pseudo-instructions are printed as the instructions they were expanded to.
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::Mipsasm;
+///
+/// let program = Mipsasm::new().base(0x8000_0000).assemble_program("
+/// loop:
+///     b loop
+///     .word 0x1234
+/// ").unwrap();
+/// assert_eq!(
+///     program.to_string(),
+///     "loop:
+///     beq        $zero, $zero, loop        // 0x80000000
+///     .word      0x00001234                // 0x80000004
+/// "
+/// );
+/// ```
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut labels = self.labels.iter().peekable();
+        let mut addr = self.base;
+        for inst in &self.insts {
+            for word in inst.get_bytes() {
+                while let Some((name, _)) = labels.next_if(|(_, a)| *a <= addr) {
+                    writeln!(f, "{}:", name)?;
+                }
+                writeln!(
+                    f,
+                    "    {:<36} // 0x{:08x}",
+                    self.render(inst, word, addr),
+                    addr
+                )?;
+                addr = addr.wrapping_add(4);
+            }
+        }
+        for (name, _) in labels {
+            writeln!(f, "{}:", name)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Program {
+    /// Serializes the label table as a JSON object mapping each label to its address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let program = Mipsasm::new().base(0x8000_0000).assemble_program("
+    /// main:
+    ///     jr $ra
+    /// ").unwrap();
+    /// assert_eq!(program.labels_json(), "{\n  \"main\": \"0x80000000\"\n}");
+    /// ```
+    pub fn labels_json(&self) -> String {
+        serde_json::to_string_pretty(&Labels(self)).unwrap()
+    }
+
+    /// Serializes the program as JSON: the label table as in [`labels_json`](Program::labels_json),
+    /// and every word with its address, the source line it came from and its source form as in
+    /// the [`Display`](fmt::Display) listing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mipsasm::Mipsasm;
+    ///
+    /// let program = Mipsasm::new().base(0x8000_0000).assemble_program("
+    /// main:
+    ///     jr $ra
+    /// ").unwrap();
+    /// let json: serde_json::Value = serde_json::from_str(&program.to_json()).unwrap();
+    /// assert_eq!(json["labels"]["main"], "0x80000000");
+    /// assert_eq!(json["words"][0]["word"], "0x03e00008");
+    /// assert_eq!(json["words"][0]["line"], 3);
+    /// assert_eq!(json["words"][0]["text"], "jr         $ra");
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut words = vec![];
+        let mut addr = self.base;
+        for (inst, source) in self.insts.iter().zip(&self.sources) {
+            for word in inst.get_bytes() {
+                words.push(serde_json::json!({
+                    "address": format!("0x{:08x}", addr),
+                    "word": format!("0x{:08x}", word),
+                    "line": source.line,
+                    "text": self.render(inst, word, addr),
+                }));
+                addr = addr.wrapping_add(4);
+            }
+        }
+        serde_json::to_string_pretty(&serde_json::json!({
+            "labels": Labels(self),
+            "words": words,
+        }))
+        .unwrap()
+    }
+}
+
+// Serializes the label table of a program as a map of names to addresses
+#[cfg(feature = "serde")]
+struct Labels<'a>(&'a Program);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Labels<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.0
+                .labels()
+                .map(|(name, addr)| (name, format!("0x{:08x}", addr))),
+        )
+    }
+}
@@ -0,0 +1,713 @@
+use crate::ast::{FloatRegister, ITypeOp, Instruction, RTypeOp, Register};
+use crate::disassembler;
+use crate::Isa;
+use std::collections::HashMap;
+use std::fmt;
+use strum_macros::{Display, EnumString};
+
+type I = ITypeOp;
+type R = RTypeOp;
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The program assembles, but probably doesn't do what was intended.
+    Warning,
+    /// The program cannot run as written.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// An advisory check of [`Program::validate`](crate::Program::validate), whose severity can be
+/// changed with [`Options::lints`](crate::Options::lints).
+///
+/// # Examples
+///
+/// ```
+/// use mipsasm::{Lint, Mipsasm, Options, Severity};
+/// use std::collections::HashMap;
+///
+/// let program = Mipsasm::new()
+///     .options(Options {
+///         lints: HashMap::from([(Lint::ZeroWrites, Some(Severity::Error))]),
+///         ..Default::default()
+///     })
+///     .assemble_program("addiu $zero, $a0, 1")
+///     .unwrap();
+/// assert_eq!(program.validate()[0].severity, Severity::Error);
+/// ```
+#[derive(Clone, Copy, Debug, Display, EnumString, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Lint {
+    /// Instructions that write to `$zero`
+    ZeroWrites,
+    /// Branches and jumps in a delay slot
+    DelaySlots,
+    /// 64-bit floating point values in odd-numbered registers
+    DoubleRegisters,
+    /// Registers read before a load into them has completed, in MIPS I code
+    LoadDelays,
+    /// Writes to HI and LO too soon after they are read
+    HiloHazards,
+}
+
+/// A problem found by [`Program::validate`](crate::Program::validate).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the problem is
+    pub severity: Severity,
+    /// The line of the source that the problem was found on, starting at 1
+    pub line: usize,
+    /// A description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: line {}: {}", self.severity, self.line, self.message)
+    }
+}
+
+// The line an instruction was assembled from and the `.set` settings in effect for it
+#[derive(Debug)]
+pub(crate) struct Source {
+    pub line: usize,
+    pub isa: Option<Isa>,
+    pub hazards: bool,
+}
+
+// A single word of an assembled program, decoded back into the machine instruction it encodes
+pub(crate) struct Word {
+    pub addr: u32,
+    pub line: usize,
+    pub isa: Option<Isa>,
+    // Whether the hazard checks apply to the word, cleared by `.set nohazards`
+    pub hazards: bool,
+    // `None` for data and words that don't decode
+    pub inst: Option<Instruction>,
+}
+
+type Check = fn(&[Word], &mut Vec<Diagnostic>);
+
+// Every check run by `Program::validate`, with the lint that sets its severity if it is advisory.
+// New checks should be added here and to its documentation
+const CHECKS: &[(Option<Lint>, Check)] = &[
+    (Some(Lint::ZeroWrites), check_zero_writes),
+    (Some(Lint::DelaySlots), check_delay_slots),
+    (Some(Lint::DoubleRegisters), check_double_registers),
+    (None, check_isa),
+    (Some(Lint::LoadDelays), check_load_delays),
+    (Some(Lint::HiloHazards), check_hilo_hazards),
+];
+
+// Runs every check over the words of a program, at the severities set for its lints
+pub(crate) fn validate(words: &[Word], lints: &HashMap<Lint, Option<Severity>>) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for (lint, check) in CHECKS {
+        let mut found = vec![];
+        check(words, &mut found);
+        if let Some(lint) = lint {
+            match lints.get(lint).copied().unwrap_or(Some(Severity::Warning)) {
+                Some(severity) => found.iter_mut().for_each(|d| d.severity = severity),
+                None => continue,
+            }
+        }
+        diagnostics.extend(found);
+    }
+    diagnostics
+}
+
+// Decodes a word of a program for validation
+pub(crate) fn decode(word: u32, addr: u32) -> Option<Instruction> {
+    disassembler::decode(word, addr)
+        .ok()
+        .filter(|inst| !matches!(inst, Instruction::Bytes { .. }))
+}
+
+// Writes to $zero are discarded, so they are almost always a mistake
+fn check_zero_writes(words: &[Word], diagnostics: &mut Vec<Diagnostic>) {
+    for word in words {
+        let dest = match &word.inst {
+            Some(Instruction::Immediate {
+                op:
+                    I::Addi
+                    | I::Addiu
+                    | I::Andi
+                    | I::Daddi
+                    | I::Daddiu
+                    | I::Lb
+                    | I::Lbu
+                    | I::Ld
+                    | I::Ldl
+                    | I::Ldr
+                    | I::Lh
+                    | I::Lhu
+                    | I::Ll
+                    | I::Lld
+                    | I::Lui
+                    | I::Lw
+                    | I::Lwl
+                    | I::Lwr
+                    | I::Lwu
+                    | I::Ori
+                    | I::Sc
+                    | I::Scd
+                    | I::Slti
+                    | I::Sltiu
+                    | I::Xori,
+                rt,
+                ..
+            }) => Some(rt),
+            // `sll $zero, $zero, 0` is the canonical `nop`
+            Some(Instruction::Register {
+                op: R::Sll,
+                rt: Register::Zero,
+                ..
+            }) => None,
+            Some(Instruction::Register { op, rt, rd, .. }) => match op {
+                R::Add
+                | R::Addu
+                | R::And
+                | R::Dadd
+                | R::Daddu
+                | R::Dsll
+                | R::Dsll32
+                | R::Dsllv
+                | R::Dsra
+                | R::Dsra32
+                | R::Dsrav
+                | R::Dsrl
+                | R::Dsrl32
+                | R::Dsrlv
+                | R::Dsub
+                | R::Dsubu
+                | R::Mfhi
+                | R::Mflo
+                | R::MovN
+                | R::Mul
+                | R::Nor
+                | R::Or
+                | R::Rdpgpr
+                | R::Sll
+                | R::Sllv
+                | R::Slt
+                | R::Sltu
+                | R::Sra
+                | R::Srav
+                | R::Srl
+                | R::Srlv
+                | R::Sub
+                | R::Subu
+                | R::Xor => Some(rd),
+                R::Cfc0 | R::Cfc1 | R::Dmfc0 | R::Dmfc1 | R::Mfc0 | R::Mfc1 => Some(rt),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(Register::Zero) = dest {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                line: word.line,
+                message: format!(
+                    "`{:?}` writes to $zero, so its result is discarded",
+                    word.inst.as_ref().unwrap()
+                ),
+            });
+        }
+    }
+}
+
+// A branch or jump in the delay slot of another has unpredictable behavior
+fn check_delay_slots(words: &[Word], diagnostics: &mut Vec<Diagnostic>) {
+    for pair in words.windows(2) {
+        if let [Word {
+            inst: Some(branch), ..
+        }, Word {
+            inst: Some(slot),
+            line,
+            ..
+        }] = pair
+        {
+            if branch.has_delay_slot() && slot.has_delay_slot() {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    line: *line,
+                    message: format!("`{:?}` is in the delay slot of `{:?}`", slot, branch),
+                });
+            }
+        }
+    }
+}
+
+// With 32-bit floating point registers, 64-bit values occupy an even/odd pair, so they must be
+// named by the even register
+fn check_double_registers(words: &[Word], diagnostics: &mut Vec<Diagnostic>) {
+    for word in words {
+        let regs = match &word.inst {
+            Some(Instruction::Immediate {
+                op: I::Ldc1 | I::Sdc1,
+                rt,
+                ..
+            }) => vec![*rt],
+            Some(Instruction::Register { op, rs, rt, rd, .. }) => match op {
+                R::AddD | R::DivD | R::MulD | R::SubD => vec![*rd, *rs, *rt],
+                R::Cd => vec![*rs, *rt],
+                R::AbsD
+                | R::CeilLD
+                | R::CvtDL
+                | R::CvtLD
+                | R::FloorLD
+                | R::MovD
+                | R::NegD
+                | R::RoundLD
+                | R::SqrtD
+                | R::TruncLD => vec![*rd, *rs],
+                R::CeilLS
+                | R::CvtDS
+                | R::CvtDW
+                | R::CvtLS
+                | R::FloorLS
+                | R::RoundLS
+                | R::TruncLS
+                | R::Dmfc1
+                | R::Dmtc1 => vec![*rd],
+                R::CeilWD
+                | R::CvtSD
+                | R::CvtSL
+                | R::CvtWD
+                | R::FloorWD
+                | R::RoundWD
+                | R::TruncWD => {
+                    vec![*rs]
+                }
+                _ => vec![],
+            },
+            _ => vec![],
+        };
+        if let Some(reg) = regs.iter().find(|reg| reg.as_num() % 2 == 1) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                line: word.line,
+                message: format!(
+                    "`{:?}` uses the odd register ${} for a 64-bit value",
+                    word.inst.as_ref().unwrap(),
+                    FloatRegister::from(*reg)
+                ),
+            });
+        }
+    }
+}
+
+// Instructions that aren't part of the ISA selected with `Options::isa` or `.set`
+fn check_isa(words: &[Word], diagnostics: &mut Vec<Diagnostic>) {
+    for word in words {
+        let (Some(isa), Some(inst)) = (word.isa, &word.inst) else {
+            continue;
+        };
+        let required = required_isa(inst);
+        if !required.iter().any(|required| isa.includes(*required)) {
+            let required = required
+                .iter()
+                .map(Isa::to_string)
+                .collect::<Vec<_>>()
+                .join(" or ");
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                line: word.line,
+                message: format!("`{:?}` requires {}, but the ISA is {}", inst, required, isa),
+            });
+        }
+    }
+}
+
+// Before MIPS II, the result of a load isn't available to the instruction that executes right after
+// it, which sees the old value of the register instead. Without an ISA the target is the R4300i,
+// which interlocks on loads, so only code explicitly assembled for MIPS I is checked
+fn check_load_delays(words: &[Word], diagnostics: &mut Vec<Diagnostic>) {
+    for (i, word) in words.iter().enumerate() {
+        let Some(load) = &word.inst else {
+            continue;
+        };
+        let Some(dest) = loaded_register(load) else {
+            continue;
+        };
+        if !word.hazards || word.isa.is_none_or(|isa| isa.includes(Isa::Mips2)) {
+            continue;
+        }
+        for next in next_executed(words, i) {
+            let Some(Word {
+                inst: Some(inst),
+                line,
+                hazards: true,
+                ..
+            }) = words.get(next)
+            else {
+                continue;
+            };
+            // `lwl` and `lwr` are forwarded the result of a load of the same register
+            if matches!(
+                inst,
+                Instruction::Immediate {
+                    op: I::Lwl | I::Lwr,
+                    ..
+                }
+            ) {
+                continue;
+            }
+            if read_registers(inst).contains(&dest) {
+                let place = if next == i + 1 {
+                    format!("right after `{:?}`", load)
+                } else {
+                    format!(
+                        "at the branch target, right after `{:?}` in the delay slot",
+                        load
+                    )
+                };
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    line: *line,
+                    message: format!(
+                        "`{:?}` reads ${} {}, so it sees the value from before the load",
+                        inst, dest, place
+                    ),
+                });
+            }
+        }
+    }
+}
+
+// Before MIPS IV, `mfhi` and `mflo` read a corrupted value if either of the next two instructions
+// writes to HI or LO
+fn check_hilo_hazards(words: &[Word], diagnostics: &mut Vec<Diagnostic>) {
+    for (i, word) in words.iter().enumerate() {
+        let Some(
+            read @ Instruction::Register {
+                op: R::Mfhi | R::Mflo,
+                ..
+            },
+        ) = &word.inst
+        else {
+            continue;
+        };
+        if !word.hazards || word.isa.is_some_and(|isa| isa.includes(Isa::Mips4)) {
+            continue;
+        }
+        for next in words.iter().skip(i + 1).take(2) {
+            if let Word {
+                inst:
+                    Some(
+                        write @ Instruction::Register {
+                            op:
+                                R::Ddiv
+                                | R::Ddivu
+                                | R::Div
+                                | R::Divu
+                                | R::Dmult
+                                | R::Dmultu
+                                | R::Mthi
+                                | R::Mtlo
+                                | R::Mult
+                                | R::Multu,
+                            ..
+                        },
+                    ),
+                line,
+                hazards: true,
+                ..
+            } = next
+            {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    line: *line,
+                    message: format!(
+                        "`{:?}` writes to HI and LO within two instructions of `{:?}`, which may read a corrupted value",
+                        write, read
+                    ),
+                });
+            }
+        }
+    }
+}
+
+// The indices of the words that may execute right after the word at `i`
+fn next_executed(words: &[Word], i: usize) -> Vec<usize> {
+    let Some(Word {
+        inst: Some(branch),
+        addr,
+        ..
+    }) = i.checked_sub(1).and_then(|prev| words.get(prev))
+    else {
+        return vec![i + 1];
+    };
+    if !branch.has_delay_slot() {
+        return vec![i + 1];
+    }
+    let target = branch.branch_target(*addr);
+    // The target of a register jump isn't known
+    if target.is_none() && branch.is_jump() {
+        return vec![];
+    }
+    let mut next = vec![];
+    if branch.is_conditional_branch() {
+        next.push(i + 1);
+    }
+    if let Some(index) = target
+        .map(|target| target.wrapping_sub(words[0].addr) / 4)
+        .filter(|index| (*index as usize) < words.len())
+    {
+        next.push(index as usize);
+    }
+    next
+}
+
+// The general purpose register written by a load, including moves from coprocessors
+fn loaded_register(inst: &Instruction) -> Option<Register> {
+    let dest = match inst {
+        Instruction::Immediate {
+            op:
+                I::Lb
+                | I::Lbu
+                | I::Ld
+                | I::Ldl
+                | I::Ldr
+                | I::Lh
+                | I::Lhu
+                | I::Ll
+                | I::Lld
+                | I::Lw
+                | I::Lwl
+                | I::Lwr
+                | I::Lwu,
+            rt,
+            ..
+        } => *rt,
+        Instruction::Register {
+            op: R::Cfc0 | R::Cfc1 | R::Dmfc0 | R::Dmfc1 | R::Mfc0 | R::Mfc1,
+            rt,
+            ..
+        } => *rt,
+        _ => return None,
+    };
+    Some(dest).filter(|dest| *dest != Register::Zero)
+}
+
+// The general purpose registers an instruction reads
+fn read_registers(inst: &Instruction) -> Vec<Register> {
+    match inst {
+        Instruction::Immediate { op, rs, rt, .. } => match op {
+            I::Beq
+            | I::Beql
+            | I::Bne
+            | I::Bnel
+            | I::Sb
+            | I::Sc
+            | I::Scd
+            | I::Sd
+            | I::Sdl
+            | I::Sdr
+            | I::Sh
+            | I::Sw
+            | I::Swl
+            | I::Swr => vec![*rs, *rt],
+            I::Addiupc
+            | I::Bc0f
+            | I::Bc0fl
+            | I::Bc0t
+            | I::Bc0tl
+            | I::Bc1f
+            | I::Bc1fl
+            | I::Bc1t
+            | I::Bc1tl
+            | I::Lui
+            | I::Lwpc => vec![],
+            _ => vec![*rs],
+        },
+        Instruction::Register { op, rs, rt, .. } => match op {
+            R::Add
+            | R::Addu
+            | R::And
+            | R::Dadd
+            | R::Daddu
+            | R::Ddiv
+            | R::Ddivu
+            | R::Div
+            | R::Divu
+            | R::Dmult
+            | R::Dmultu
+            | R::Dsllv
+            | R::Dsrav
+            | R::Dsrlv
+            | R::Dsub
+            | R::Dsubu
+            | R::MovN
+            | R::Mul
+            | R::Mult
+            | R::Multu
+            | R::Nor
+            | R::Or
+            | R::Sllv
+            | R::Slt
+            | R::Sltu
+            | R::Srav
+            | R::Srlv
+            | R::Sub
+            | R::Subu
+            | R::Teq
+            | R::Tge
+            | R::Tgeu
+            | R::Tlt
+            | R::Tltu
+            | R::Tne
+            | R::Xor => vec![*rs, *rt],
+            R::Ctc0
+            | R::Ctc1
+            | R::Dmtc0
+            | R::Dmtc1
+            | R::Dsll
+            | R::Dsll32
+            | R::Dsra
+            | R::Dsra32
+            | R::Dsrl
+            | R::Dsrl32
+            | R::Mtc0
+            | R::Mtc1
+            | R::Sll
+            | R::Sra
+            | R::Srl
+            | R::Wrpgpr => vec![*rt],
+            R::Jalr | R::JalrHb | R::Jr | R::JrHb | R::Mthi | R::Mtlo => vec![*rs],
+            _ => vec![],
+        },
+        _ => vec![],
+    }
+}
+
+// The ISAs that introduced an instruction, any of which provides it
+fn required_isa(inst: &Instruction) -> &'static [Isa] {
+    match inst {
+        Instruction::Immediate { op, .. } => match op {
+            I::Bc0fl
+            | I::Bc0tl
+            | I::Bc1fl
+            | I::Bc1tl
+            | I::Beql
+            | I::Bgezall
+            | I::Bgezl
+            | I::Bgtzl
+            | I::Blezl
+            | I::Bltzall
+            | I::Bltzl
+            | I::Bnel
+            | I::Ldc1
+            | I::Ll
+            | I::Sc
+            | I::Sdc1
+            | I::Teqi
+            | I::Tgei
+            | I::Tgeiu
+            | I::Tlti
+            | I::Tltiu
+            | I::Tnei => &[Isa::Mips2],
+            // Part of the R4000's system control coprocessor, which MIPS32 adopted
+            I::Cache => &[Isa::Mips3, Isa::Mips32],
+            I::Daddi
+            | I::Daddiu
+            | I::Ld
+            | I::Ldl
+            | I::Ldr
+            | I::Lld
+            | I::Lwu
+            | I::Scd
+            | I::Sd
+            | I::Sdl
+            | I::Sdr => &[Isa::Mips3],
+            I::Pref => &[Isa::Mips4],
+            I::Synci => &[Isa::Mips32r2],
+            I::Addiupc | I::Lwpc => &[Isa::Mips32r6],
+            _ => &[Isa::Mips1],
+        },
+        Instruction::Register { op, .. } => match op {
+            R::CeilWD
+            | R::CeilWS
+            | R::FloorWD
+            | R::FloorWS
+            | R::RoundWD
+            | R::RoundWS
+            | R::SqrtD
+            | R::SqrtS
+            | R::Sync
+            | R::Teq
+            | R::Tge
+            | R::Tgeu
+            | R::Tlt
+            | R::Tltu
+            | R::Tne
+            | R::TruncWD
+            | R::TruncWS => &[Isa::Mips2],
+            R::CeilLD
+            | R::CeilLS
+            | R::CvtDL
+            | R::CvtLD
+            | R::CvtLS
+            | R::CvtSL
+            | R::Dadd
+            | R::Daddu
+            | R::Ddiv
+            | R::Ddivu
+            | R::Dmfc0
+            | R::Dmfc1
+            | R::Dmtc0
+            | R::Dmtc1
+            | R::Dmult
+            | R::Dmultu
+            | R::Dsll
+            | R::Dsll32
+            | R::Dsllv
+            | R::Dsra
+            | R::Dsra32
+            | R::Dsrav
+            | R::Dsrl
+            | R::Dsrl32
+            | R::Dsrlv
+            | R::Dsub
+            | R::Dsubu
+            | R::FloorLD
+            | R::FloorLS
+            | R::RoundLD
+            | R::RoundLS
+            | R::TruncLD
+            | R::TruncLS => &[Isa::Mips3],
+            // Part of the R4000's system control coprocessor, which MIPS32 adopted
+            R::Eret => &[Isa::Mips3, Isa::Mips32],
+            R::MovN => &[Isa::Mips4],
+            R::AbsPs
+            | R::AddPs
+            | R::Cps
+            | R::CvtPsS
+            | R::CvtSPl
+            | R::CvtSPu
+            | R::MovPs
+            | R::MulPs
+            | R::NegPs
+            | R::PllPs
+            | R::PluPs
+            | R::PulPs
+            | R::PuuPs
+            | R::SubPs => &[Isa::Mips5],
+            R::Deret | R::Mul | R::Wait => &[Isa::Mips32],
+            R::Di | R::Ei | R::JalrHb | R::JrHb | R::Pause | R::Rdpgpr | R::Wrpgpr => {
+                &[Isa::Mips32r2]
+            }
+            _ => &[Isa::Mips1],
+        },
+        _ => &[Isa::Mips1],
+    }
+}
@@ -5,6 +5,8 @@ pub fn asm(inst: &str) -> Vec<u32> {
     get_bytes(&insts)
 }
 
+// Not every test binary that includes this module disassembles
+#[allow(dead_code)]
 pub fn disasm(inst: &[u32]) -> String {
     let x = Mipsasm::new().base(0x80000000).debug().disassemble(inst);
     x.first().unwrap().clone()
@@ -14,6 +14,15 @@ fn test_abs() {
 test!(test_add, "add $a0, $a1, $a2", 0x00a62020);
 test!(test_addi, "addi $a0, $a1, 0x8", 0x20a40008);
 test!(test_addiu, "addiu $a0, $a1, 0x8", 0x24a40008);
+
+#[test]
+fn test_addiupc() {
+    let inst = asm("addiupc $a0, data\nnop\ndata:\n.word 0x1234");
+    assert_eq!(inst, vec![0xec800002, 0x00000000, 0x00001234]);
+    assert_eq!(disasm(&inst), "addiupc $a0, 0x2");
+    assert_eq!(asm("addiupc $a0, 0x80000010"), vec![0xec800004]);
+}
+
 test!(test_addu, "addu $a0, $a1, $a2", 0x00a62021);
 test!(test_and, "and $a0, $a1, $a2", 0x00a62024);
 test!(test_andi, "andi $a0, $a1, 0x8", 0x30a40008);
@@ -200,6 +209,9 @@ fn test_clear() {
     assert_eq!(inst, vec![0x00002021]);
 }
 
+test!(test_cop0, "cop0 0x3f", 0x4200003f);
+test!(test_cop1, "cop1 0x1e00000", 0x47e00000);
+test!(test_cop2, "cop2 0x123456", 0x4a123456);
 test!(test_ctc0, "ctc0 $a0, Context", 0x40c42000);
 test!(test_ctc1, "ctc1 $a0, $fa1", 0x44c47000);
 
@@ -235,6 +247,9 @@ fn test_ddivu_pseudo() {
     assert_eq!(inst, vec![0x00c001f4, 0x00a6001f, 0x00002012]);
 }
 
+test!(test_deret, "deret", 0x4200001f);
+test!(test_di, "di", 0x41606000);
+test!(test_di_rt, "di $t0", 0x41686000);
 test!(test_div, "div $a0, $a1", 0x0085001a);
 test!(test_divu, "divu $a0, $a1", 0x0085001b);
 
@@ -256,6 +271,15 @@ fn test_divu_pseudo() {
     assert_eq!(inst, vec![0x00c001f4, 0x00a6001b, 0x00002012]);
 }
 
+#[test]
+fn test_div_pseudo_zero_rd() {
+    // Without a destination there is no quotient to move, so only the division is emitted
+    assert_eq!(asm("div $zero, $a1, $a2"), vec![0x00a6001a]);
+    assert_eq!(asm("divu $zero, $a1, $a2"), vec![0x00a6001b]);
+}
+
+test!(test_div_zero_divisor, "div $a0, $zero", 0x0080001a);
+
 #[test]
 fn test_dli_pos() {
     let inst = asm("dli $a0, 0x123456789abcdef0");
@@ -381,6 +405,9 @@ fn test_dsubiu() {
 }
 
 test!(test_dsubu, "dsubu $a0, $a1, $a2", 0x00a6202f);
+test!(test_ehb, "ehb", 0x000000c0);
+test!(test_ei, "ei", 0x41606020);
+test!(test_ei_rt, "ei $t0", 0x41686020);
 test!(test_eret, "eret", 0x42000018);
 test!(test_j, "j 0x80000000", 0x08000000);
 test!(test_jal, "jal 0x80000000", 0x0c000000);
@@ -409,6 +436,9 @@ fn test_jal_sym() {
 test!(test_jr, "jr $ra", 0x03e00008);
 test!(test_jalr, "jalr $ra", 0x03e0f809);
 test!(test_jalr_two_args, "jalr $a0, $a1", 0x00a02009);
+test!(test_jr_hb, "jr.hb $ra", 0x03e00408);
+test!(test_jalr_hb, "jalr.hb $t9", 0x0320fc09);
+test!(test_jalr_hb_two_args, "jalr.hb $a0, $a1", 0x00a02409);
 test!(test_lb, "lb $a0, 0x10($a1)", 0x80a40010);
 test!(test_lbu, "lbu $a0, 0x10($a1)", 0x90a40010);
 test!(test_ld, "ld $a0, 0x10($a1)", 0xdca40010);
@@ -437,6 +467,14 @@ fn test_li() {
 test!(test_lui, "lui $a0, 0x8000", 0x3c048000);
 test!(test_lw, "lw $a0, 0x10($a1)", 0x8ca40010);
 test!(test_lwl, "lwl $a0, 0x10($a1)", 0x88a40010);
+
+#[test]
+fn test_lwpc() {
+    let inst = asm("loop:\nnop\nlwpc $t0, loop");
+    assert_eq!(inst, vec![0x00000000, 0xed0fffff]);
+    assert_eq!(disasm(&inst[1..]), "lwpc $t0, -0x1");
+}
+
 test!(test_lwr, "lwr $a0, 0x10($a1)", 0x98a40010);
 
 #[test]
@@ -481,6 +519,7 @@ test!(test_mult, "mult $a0, $a1", 0x00850018);
 test!(test_multu, "multu $a0, $a1", 0x00850019);
 test!(test_mtc0, "mtc0 $a0, Context", 0x40842000);
 test!(test_mtc1, "mtc1 $a0, $fa1", 0x44847000);
+test!(test_movn, "movn $a0, $a1, $a2", 0x00a6200b);
 test!(test_mthi, "mthi $a0", 0x00800011);
 test!(test_mtlo, "mtlo $a0", 0x00800013);
 
@@ -512,6 +551,9 @@ fn test_not() {
 
 test!(test_or, "or $a0, $a1, $a2", 0x00a62025);
 test!(test_ori, "ori $a0, $a1, 0x8", 0x34a40008);
+test!(test_pause, "pause", 0x00000140);
+test!(test_pref, "pref 0x1, 0x10($a0)", 0xcc810010);
+test!(test_rdpgpr, "rdpgpr $a0, $a1", 0x41452000);
 
 #[test]
 fn test_rem() {
@@ -600,6 +642,7 @@ test!(test_sra, "sra $a0, $a1, 0x1f", 0x000527c3);
 test!(test_srav, "srav $a0, $a1, $a2", 0x00c52007);
 test!(test_srl, "srl $a0, $a1, 0x1f", 0x000527c2);
 test!(test_srlv, "srlv $a0, $a1, $a2", 0x00c52006);
+test!(test_ssnop, "ssnop", 0x00000040);
 test!(test_sub, "sub $a0, $a1, $a2", 0x00a62022);
 
 #[test]
@@ -620,11 +663,16 @@ test!(test_swc1, "swc1 $ft0, 0x8($a0)", 0xe4840008);
 test!(test_swl, "swl $a0, 0x10($a1)", 0xa8a40010);
 test!(test_swr, "swr $a0, 0x10($a1)", 0xb8a40010);
 test!(test_sync, "sync", 0x0000000f);
+test!(test_sync_stype, "sync 0x10", 0x0000040f);
+test!(test_synci, "synci 0x10($a0)", 0x049f0010);
+test!(test_synci_neg, "synci -0x4($sp)", 0x07bffffc);
 test!(test_syscall, "syscall", 0x0000000c);
 test!(test_syscall_imm, "syscall 0x20", 0x0000080c);
 test!(test_teq, "teq $a0, $a1", 0x00850034);
+test!(test_teq_code, "teq $a0, $a1, 0x7", 0x008501f4);
 test!(test_teqi, "teqi $a0, 0x20", 0x048c0020);
 test!(test_tge, "tge $a0, $a1", 0x00850030);
+test!(test_tge_code, "tge $a0, $a1, 0x3ff", 0x0085fff0);
 test!(test_tgei, "tgei $a0, 0x20", 0x04880020);
 test!(test_tgeiu, "tgeiu $a0, 0x20", 0x04890020);
 test!(test_tgeu, "tgeu $a0, $a1", 0x00850031);
@@ -637,6 +685,10 @@ test!(test_tlti, "tlti $a0, 0x20", 0x048a0020);
 test!(test_tltiu, "tltiu $a0, 0x20", 0x048b0020);
 test!(test_tltu, "tltu $a0, $a1", 0x00850033);
 test!(test_tne, "tne $a0, $a1", 0x00850036);
+test!(test_tne_code, "tne $a0, $a1, 0x20", 0x00850836);
 test!(test_tnei, "tnei $a0, 0x20", 0x048e0020);
+test!(test_wait, "wait", 0x42000020);
+test!(test_wait_code, "wait 0x123", 0x420048e0);
+test!(test_wrpgpr, "wrpgpr $a0, $a1", 0x41c52000);
 test!(test_xor, "xor $a0, $a1, $a2", 0x00a62026);
 test!(test_xori, "xori $a0, $a1, 0x8", 0x38a40008);
@@ -0,0 +1,363 @@
+use mipsasm::{Mipsasm, MnemonicWidth};
+use std::collections::HashMap;
+
+#[test]
+fn test_annotations() {
+    let annotations = HashMap::from_iter(vec![(0x4, "write"), (0x7, "divide by zero")]);
+    let insts = Mipsasm::new()
+        .base(0x80000000)
+        .annotations(annotations)
+        .disassemble(&[0x0000010c, 0x00a001f4, 0x0000000d]);
+    assert_eq!(
+        insts,
+        vec![
+            "func_80000000:",
+            "syscall    0x4  # write",
+            "teq        $a1, $zero, 0x7  # divide by zero",
+            "break",
+        ]
+    );
+}
+
+#[test]
+fn test_annotate_calls() {
+    let syms = HashMap::from([(0x80000000, "main"), (0x80000100, "puts")]);
+    // jal puts; jal 0x80000200; bnez $a0, main; bnez $a0, 0x80000010
+    let words = [0x0c000040, 0x0c000080, 0x1480fffd, 0x14800000];
+    let disassemble = |annotate| {
+        let mut mipsasm = Mipsasm::new();
+        mipsasm.base(0x80000000).symbols(syms.clone()).debug();
+        if annotate {
+            mipsasm.annotate_calls();
+        }
+        mipsasm.disassemble(&words)
+    };
+    assert_eq!(
+        disassemble(false),
+        vec![
+            "jal puts",
+            "jal 0x80000200",
+            "bne $a0, $zero, -0x3",
+            "bne $a0, $zero, 0x0"
+        ]
+    );
+    assert_eq!(
+        disassemble(true),
+        vec![
+            "jal puts  # 0x80000100",
+            "jal 0x80000200",
+            "bne $a0, $zero, main  # 0x80000000",
+            "bne $a0, $zero, 0x0"
+        ]
+    );
+}
+
+#[test]
+fn test_annotate_calls_address_wraps() {
+    let syms = HashMap::from([(0xfffffffc, "top")]);
+    // b top; b 0x4, past the end of the address space
+    let insts = Mipsasm::new()
+        .base(0xfffffffc)
+        .symbols(syms)
+        .debug()
+        .annotate_calls()
+        .disassemble(&[0x1000ffff, 0x10000000]);
+    assert_eq!(
+        insts,
+        vec![
+            "beq $zero, $zero, top  # 0xfffffffc",
+            "beq $zero, $zero, 0x0"
+        ]
+    );
+}
+
+#[test]
+fn test_no_annotations() {
+    let insts = Mipsasm::new()
+        .base(0x80000000)
+        .debug()
+        .disassemble(&[0x0000010c]);
+    assert_eq!(insts, vec!["syscall 0x4"]);
+}
+
+#[test]
+fn test_decode_errors() {
+    let (insts, errors) = Mipsasm::new()
+        .base(0x80000000)
+        .debug()
+        .disassemble_checked(&[0x7c000000, 0x00a62020, 0x78000000]);
+    assert_eq!(
+        insts,
+        vec![".word 0x7c000000", "add $a0, $a1, $a2", ".word 0x78000000"]
+    );
+    assert_eq!(
+        errors.iter().map(|e| (e.addr, e.word)).collect::<Vec<_>>(),
+        vec![(0x80000000, 0x7c000000), (0x80000008, 0x78000000)]
+    );
+}
+
+#[test]
+fn test_split_at_symbols() {
+    let syms = HashMap::from_iter(vec![(0x80000008, "helper"), (0x80000014, "tail")]);
+    let words = [
+        0x0c000002, // jal helper
+        0x00000000, // nop
+        0x00000000, // nop
+        0x03e00008, // jr $ra
+        0x00000000, // nop
+        0x08000005, // j tail
+        0x00000000, // nop
+    ];
+    let insts = Mipsasm::new()
+        .base(0x80000000)
+        .symbols(syms.clone())
+        .split_at_symbols()
+        .disassemble(&words);
+    assert_eq!(
+        insts,
+        vec![
+            "func_80000000:",
+            "jal        helper",
+            "sll        $zero, $zero, 0x0",
+            "",
+            "helper:",
+            "sll        $zero, $zero, 0x0",
+            "jr         $ra",
+            "sll        $zero, $zero, 0x0",
+            "",
+            "tail:",
+            "j          tail",
+            "sll        $zero, $zero, 0x0",
+        ]
+    );
+
+    let insts = Mipsasm::new()
+        .base(0x80000000)
+        .symbols(syms)
+        .disassemble(&words);
+    assert!(!insts.contains(&"helper:".to_string()));
+}
+
+#[test]
+fn test_split_at_symbols_address_wraps() {
+    let syms = HashMap::from([(0x4, "low")]);
+    // jr $ra; nop; nop, running from the top of the address space back to 0
+    let insts = Mipsasm::new()
+        .base(0xfffffffc)
+        .symbols(syms)
+        .split_at_symbols()
+        .disassemble(&[0x03e00008, 0, 0]);
+    assert_eq!(
+        insts,
+        vec![
+            "func_fffffffc:",
+            "jr         $ra",
+            "sll        $zero, $zero, 0x0",
+            "",
+            "low:",
+            "sll        $zero, $zero, 0x0",
+        ]
+    );
+}
+
+#[test]
+fn test_numeric_regs() {
+    let words = [0x01095020, 0x8fbf0014, 0x46062100, 0x44813000, 0x40086000];
+    let insts = Mipsasm::new()
+        .base(0x80000000)
+        .numeric_regs()
+        .disassemble(&words);
+    assert_eq!(
+        insts,
+        vec![
+            "func_80000000:",
+            "add        $10, $8, $9",
+            "lw         $31, 0x14($29)",
+            "add.s      $f4, $f4, $f6",
+            "mtc1       $1, $f6",
+            "mfc0       $8, Status",
+        ]
+    );
+
+    let insts = Mipsasm::new()
+        .base(0x80000000)
+        .numeric_regs()
+        .debug()
+        .disassemble(&words[..2]);
+    assert_eq!(insts, vec!["add $10, $8, $9", "lw $31, 0x14($29)"]);
+}
+
+#[test]
+fn test_disassemble_iter() {
+    let words = [0x00a62020, 0x7c000000, 0x03e00008];
+    let insts = mipsasm::disassemble_iter(words.iter().copied(), 0x80000000).collect::<Vec<_>>();
+    assert_eq!(
+        insts.iter().map(|(addr, _)| *addr).collect::<Vec<_>>(),
+        vec![0x80000000, 0x80000004, 0x80000008]
+    );
+    assert_eq!(
+        format!("{:?}", insts[0].1.as_ref().unwrap()),
+        "add $a0, $a1, $a2"
+    );
+    assert_eq!(insts[1].1.as_ref().unwrap_err().word, 0x7c000000);
+    assert_eq!(format!("{:?}", insts[2].1.as_ref().unwrap()), "jr $ra");
+
+    // Only the words that are asked for are decoded
+    let mut decoded = 0;
+    let window =
+        mipsasm::disassemble_iter(std::iter::repeat(0).inspect(|_| decoded += 1), 0x80000000)
+            .skip(100)
+            .take(2)
+            .count();
+    assert_eq!((window, decoded), (2, 102));
+}
+
+#[test]
+fn test_data_ranges() {
+    let (insts, errors) = Mipsasm::new()
+        .base(0x80000000)
+        .data_range(0x80000004..0x80000008)
+        .data_range(0x8000000c..0x80000010)
+        .debug()
+        .disassemble_checked(&[0x03e00008, 0x7c000000, 0x00a62020, 0x00a62020]);
+    assert_eq!(
+        insts,
+        vec![
+            "jr $ra",
+            ".word 0x7c000000",
+            "add $a0, $a1, $a2",
+            ".word 0x00a62020"
+        ]
+    );
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn test_data_range_round_trip() {
+    let words = [
+        0x27bdffe8, 0x0c000010, 0x00000000, 0x03e00008, 0x27bd0018, 0xdeadbeef, 0x7c000000,
+        0x00000000, 0x00800000,
+    ];
+    for width in [None, Some(MnemonicWidth::Tab)] {
+        let mut mipsasm = Mipsasm::new();
+        mipsasm.base(0x80000000).data_range(0x80000014..0x80000024);
+        if let Some(width) = width {
+            mipsasm.mnemonic_width(width);
+        }
+        let text = mipsasm.disassemble(&words).join("\n");
+        assert!(text.contains(".word"));
+        let insts = Mipsasm::new().base(0x80000000).assemble(&text).unwrap();
+        assert_eq!(mipsasm::get_bytes(&insts), words, "{}", text);
+    }
+}
+
+#[test]
+fn test_control_flow_queries() {
+    let decode = |word| {
+        mipsasm::disassemble_iter([word], 0x80000010)
+            .next()
+            .unwrap()
+            .1
+            .unwrap()
+    };
+
+    // b 0x80000000
+    let b = decode(0x1000fffb);
+    assert!(b.is_branch() && !b.is_jump() && !b.is_conditional_branch() && b.has_delay_slot());
+    assert_eq!(b.branch_target(0x80000010), Some(0x80000000));
+
+    // bnez $a0, 0x80000020
+    let bnez = decode(0x14800003);
+    assert!(bnez.is_branch() && bnez.is_conditional_branch());
+    assert_eq!(bnez.branch_target(0x80000010), Some(0x80000020));
+
+    // jal 0x80000100
+    let jal = decode(0x0c000040);
+    assert!(jal.is_jump() && !jal.is_branch() && jal.has_delay_slot());
+    assert_eq!(jal.branch_target(0x80000010), Some(0x80000100));
+
+    // jr $ra
+    let jr = decode(0x03e00008);
+    assert!(jr.is_jump() && jr.has_delay_slot());
+    assert_eq!(jr.branch_target(0x80000010), None);
+
+    // addu $a0, $a1, $a2
+    let addu = decode(0x00a62021);
+    assert!(!addu.is_branch() && !addu.is_jump() && !addu.has_delay_slot());
+    assert_eq!(addu.branch_target(0x80000010), None);
+}
+
+#[test]
+fn test_mnemonic_width() {
+    let words = [0x0000010c, 0x3c088000, 0x46062100, 0x46047032];
+    let annotations = HashMap::from_iter(vec![(0x4, "write")]);
+    let insts = Mipsasm::new()
+        .annotations(annotations.clone())
+        .mnemonic_width(MnemonicWidth::Columns(6))
+        .disassemble(&words);
+    assert_eq!(
+        insts,
+        vec![
+            "func_00000000:",
+            "syscall 0x4  # write",
+            "lui   $t0, 0x8000",
+            "add.s $ft0, $ft0, $ft1",
+            "c.eq.s $fa1, $ft0",
+        ]
+    );
+
+    let insts = Mipsasm::new()
+        .annotations(annotations)
+        .mnemonic_width(MnemonicWidth::Tab)
+        .numeric_regs()
+        .disassemble(&words);
+    assert_eq!(
+        insts,
+        vec![
+            "func_00000000:",
+            "syscall\t0x4  # write",
+            "lui\t$8, 0x8000",
+            "add.s\t$f4, $f4, $f6",
+            "c.eq.s\t$f14, $f4",
+        ]
+    );
+}
+
+#[test]
+fn test_cop0_co_bit() {
+    let (insts, errors) = Mipsasm::new()
+        .base(0x80000000)
+        .debug()
+        .disassemble_checked(&[0x42000018, 0x42000002, 0x40600018, 0x42000418]);
+    assert_eq!(
+        insts,
+        vec!["eret", "tlbwi", ".word 0x40600018", "cop0 0x418"]
+    );
+    assert_eq!(
+        errors.iter().map(|e| (e.addr, e.word)).collect::<Vec<_>>(),
+        vec![(0x80000008, 0x40600018)]
+    );
+}
+
+#[test]
+fn test_read_nm_symbols() {
+    let words = [0x0c000400, 0x0c000800];
+    let nm = "80001000 T main\n         U printf\n80002000 t helper\n80003000\n";
+    let objdump = "main.o:     file format elf32-tradbigmips
+
+SYMBOL TABLE:
+80001000 g     F .text\t00000040 main
+80002000 l     F .text\t00000010 helper
+00000000         *UND*\t00000000 printf
+zzzzzzzz g     F .text\t00000010 bad";
+    for (text, skipped) in [(nm, vec![4]), (objdump, vec![7])] {
+        let (syms, lines) = mipsasm::read_nm_symbols(text);
+        assert_eq!(lines, skipped);
+        let insts = Mipsasm::new()
+            .base(0x80000000)
+            .symbols(syms)
+            .debug()
+            .disassemble(&words);
+        assert_eq!(insts, vec!["jal main", "jal helper"]);
+    }
+}
@@ -3,10 +3,13 @@ use common::{asm, disasm};
 
 test!(test_abs_s, "abs.s $fa0, $fa1", 0x46007305);
 test!(test_abs_d, "abs.d $fa0, $fa1", 0x46207305);
+test!(test_abs_ps, "abs.ps $fa0, $fa1", 0x46c07305);
 test!(test_add_s, "add.s $fa0, $fa1, $ft0", 0x46047300);
 test!(test_add_d, "add.d $fa0, $fa1, $ft0", 0x46247300);
+test!(test_add_ps, "add.ps $fa0, $fa1, $ft0", 0x46c47300);
 test!(test_c_eq_s, "c.eq.s $fa1, $ft0", 0x46047032);
 test!(test_c_eq_d, "c.eq.d $fa1, $ft0", 0x46247032);
+test!(test_c_eq_ps, "c.eq.ps $fa1, $ft0", 0x46c47032);
 test!(test_c_f_s, "c.f.s $fa1, $ft0", 0x46047030);
 test!(test_c_f_d, "c.f.d $fa1, $ft0", 0x46247030);
 test!(test_c_le_s, "c.le.s $fa1, $ft0", 0x4604703e);
@@ -19,12 +22,14 @@ test!(test_c_ngl_s, "c.ngl.s $fa1, $ft0", 0x4604703b);
 test!(test_c_ngl_d, "c.ngl.d $fa1, $ft0", 0x4624703b);
 test!(test_c_ngle_s, "c.ngle.s $fa1, $ft0", 0x46047039);
 test!(test_c_ngle_d, "c.ngle.d $fa1, $ft0", 0x46247039);
+test!(test_c_ngle_ps, "c.ngle.ps $fa1, $ft0", 0x46c47039);
 test!(test_c_ngt_s, "c.ngt.s $fa1, $ft0", 0x4604703f);
 test!(test_c_ngt_d, "c.ngt.d $fa1, $ft0", 0x4624703f);
 test!(test_c_ole_s, "c.ole.s $fa1, $ft0", 0x46047036);
 test!(test_c_ole_d, "c.ole.d $fa1, $ft0", 0x46247036);
 test!(test_c_olt_s, "c.olt.s $fa1, $ft0", 0x46047034);
 test!(test_c_olt_d, "c.olt.d $fa1, $ft0", 0x46247034);
+test!(test_c_olt_ps, "c.olt.ps $fa1, $ft0", 0x46c47034);
 test!(test_c_seq_s, "c.seq.s $fa1, $ft0", 0x4604703a);
 test!(test_c_seq_d, "c.seq.d $fa1, $ft0", 0x4624703a);
 test!(test_c_sf_s, "c.sf.s $fa1, $ft0", 0x46047038);
@@ -46,8 +51,11 @@ test!(test_cvt_d_s, "cvt.d.s $fa0, $fa1", 0x46007321);
 test!(test_cvt_d_w, "cvt.d.w $fa0, $fa1", 0x46807321);
 test!(test_cvt_l_d, "cvt.l.d $fa0, $fa1", 0x46207325);
 test!(test_cvt_l_s, "cvt.l.s $fa0, $fa1", 0x46007325);
+test!(test_cvt_ps_s, "cvt.ps.s $fa0, $fa1, $ft0", 0x46047326);
 test!(test_cvt_s_d, "cvt.s.d $fa0, $fa1", 0x46207320);
 test!(test_cvt_s_l, "cvt.s.l $fa0, $fa1", 0x46a07320);
+test!(test_cvt_s_pl, "cvt.s.pl $fa0, $fa1", 0x46c07328);
+test!(test_cvt_s_pu, "cvt.s.pu $fa0, $fa1", 0x46c07320);
 test!(test_cvt_s_w, "cvt.s.w $fa0, $fa1", 0x46807320);
 test!(test_cvt_w_d, "cvt.w.d $fa0, $fa1", 0x46207324);
 test!(test_cvt_w_s, "cvt.w.s $fa0, $fa1", 0x46007324);
@@ -59,10 +67,17 @@ test!(test_floor_w_s, "floor.w.s $fa0, $fa1", 0x4600730f);
 test!(test_floor_w_d, "floor.w.d $fa0, $fa1", 0x4620730f);
 test!(test_mul_s, "mul.s $fa0, $fa1, $ft0", 0x46047302);
 test!(test_mul_d, "mul.d $fa0, $fa1, $ft0", 0x46247302);
+test!(test_mul_ps, "mul.ps $fa0, $fa1, $ft0", 0x46c47302);
 test!(test_mov_s, "mov.s $fa0, $fa1", 0x46007306);
 test!(test_mov_d, "mov.d $fa0, $fa1", 0x46207306);
+test!(test_mov_ps, "mov.ps $fa0, $fa1", 0x46c07306);
 test!(test_neg_s, "neg.s $fa0, $fa1", 0x46007307);
 test!(test_neg_d, "neg.d $fa0, $fa1", 0x46207307);
+test!(test_neg_ps, "neg.ps $fa0, $fa1", 0x46c07307);
+test!(test_pll_ps, "pll.ps $fa0, $fa1, $ft0", 0x46c4732c);
+test!(test_plu_ps, "plu.ps $fa0, $fa1, $ft0", 0x46c4732d);
+test!(test_pul_ps, "pul.ps $fa0, $fa1, $ft0", 0x46c4732e);
+test!(test_puu_ps, "puu.ps $fa0, $fa1, $ft0", 0x46c4732f);
 test!(test_round_l_s, "round.l.s $fa0, $fa1", 0x46007308);
 test!(test_round_l_d, "round.l.d $fa0, $fa1", 0x46207308);
 test!(test_round_w_s, "round.w.s $fa0, $fa1", 0x4600730c);
@@ -71,6 +86,7 @@ test!(test_sqrt_s, "sqrt.s $fa0, $fa1", 0x46007304);
 test!(test_sqrt_d, "sqrt.d $fa0, $fa1", 0x46207304);
 test!(test_sub_s, "sub.s $fa0, $fa1, $ft0", 0x46047301);
 test!(test_sub_d, "sub.d $fa0, $fa1, $ft0", 0x46247301);
+test!(test_sub_ps, "sub.ps $fa0, $fa1, $ft0", 0x46c47301);
 test!(test_trunc_l_s, "trunc.l.s $fa0, $fa1", 0x46007309);
 test!(test_trunc_l_d, "trunc.l.d $fa0, $fa1", 0x46207309);
 test!(test_trunc_w_s, "trunc.w.s $fa0, $fa1", 0x4600730d);
@@ -0,0 +1,870 @@
+mod common;
+use mipsasm::{get_bytes, Isa, Mipsasm, Options, ParserError};
+use std::collections::HashMap;
+
+use common::asm;
+
+#[test]
+fn test_line_too_long() {
+    let input = format!("addi $a0, $a0, {}", "1".repeat(5000));
+    let errs = Mipsasm::new().assemble(&input).unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::LineTooLong {
+            length: 5015,
+            max: 4096,
+            ..
+        }]
+    ));
+    assert!(errs[0].to_string().contains("5015 bytes long"));
+}
+
+#[test]
+fn test_too_many_operands() {
+    let input = format!("add $a0{}", ", $a0".repeat(100));
+    let errs = Mipsasm::new().assemble(&input).unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::TooManyOperands {
+            found: 101,
+            max: 64,
+            ..
+        }]
+    ));
+    assert!(errs[0].to_string().contains("101 found"));
+}
+
+#[test]
+fn test_limits_overridable() {
+    let errs = Mipsasm::new()
+        .options(Options {
+            max_line_length: 8,
+            ..Default::default()
+        })
+        .assemble("add $a0, $a1, $a2")
+        .unwrap_err();
+    assert!(matches!(errs.as_slice(), [ParserError::LineTooLong { .. }]));
+
+    let errs = Mipsasm::new()
+        .options(Options {
+            max_operands: 2,
+            ..Default::default()
+        })
+        .assemble("add $a0, $a1, $a2")
+        .unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::TooManyOperands { .. }]
+    ));
+
+    assert!(Mipsasm::new()
+        .options(Options {
+            max_operands: 3,
+            ..Default::default()
+        })
+        .assemble("add $a0, $a1, $a2")
+        .is_ok());
+}
+
+#[test]
+fn test_align() {
+    assert_eq!(asm("nop\n.align\nnop"), vec![0, 0]);
+    assert_eq!(asm("nop\n.align 2\nnop"), vec![0, 0]);
+    assert_eq!(asm("nop\n.align 3, 0x00\nnop"), vec![0, 0, 0]);
+    assert_eq!(asm("nop\n.align 3, 0xff\nnop"), vec![0, 0xffffffff, 0]);
+    assert_eq!(asm("nop\nnop\n.align 3, 0xff\nnop"), vec![0, 0, 0]);
+}
+
+#[test]
+fn test_align_labels() {
+    assert_eq!(
+        asm("nop\n.align 4\nfoo:\nb foo\nj foo"),
+        vec![0, 0, 0, 0, 0x1000ffff, 0x08000004]
+    );
+}
+
+#[test]
+fn test_align_invalid() {
+    assert!(Mipsasm::new().assemble(".align 3, 0x100").is_err());
+    assert!(Mipsasm::new().assemble(".align 32").is_err());
+    assert!(Mipsasm::new().assemble(".align 3, 0, 0").is_err());
+}
+
+#[test]
+fn test_label_after_pseudo() {
+    assert_eq!(
+        asm("foo:\nli $t0, 0x12345678\nb foo\nnop"),
+        vec![0x3c081234, 0x35085678, 0x1000fffd, 0]
+    );
+}
+
+#[test]
+fn test_neg_format_routing() {
+    assert_eq!(asm("neg $t0, $t1"), vec![0x00094022]);
+    assert_eq!(asm("neg.s $f0, $f2"), vec![0x46001007]);
+    assert_eq!(asm("NEG.D $f0, $f2"), vec![0x46201007]);
+}
+
+#[test]
+fn test_invalid_format() {
+    for inst in [
+        "neg.x $f0, $f2",
+        "abs.w $f0, $f2",
+        "add. $f0, $f2, $f4",
+        "c.eq.q $f0, $f2",
+        "cvt.s.s $f0, $f2",
+    ] {
+        let errs = Mipsasm::new().assemble(inst).unwrap_err();
+        assert!(
+            matches!(errs.as_slice(), [ParserError::InvalidFormat { .. }]),
+            "{}",
+            inst
+        );
+        assert!(errs[0].to_string().contains("invalid format"));
+    }
+    for inst in ["x", "foo.s $f0, $f2"] {
+        let errs = Mipsasm::new().assemble(inst).unwrap_err();
+        assert!(
+            matches!(errs.as_slice(), [ParserError::InvalidOpcode { .. }]),
+            "{}",
+            inst
+        );
+    }
+}
+
+#[test]
+fn test_offset_base() {
+    assert_eq!(asm("lw $a0, 0x10($sp)"), vec![0x8fa40010]);
+    assert_eq!(asm("lw $a0, ($sp)"), vec![0x8fa40000]);
+    assert_eq!(asm("sw $a0, -4($sp)"), vec![0xafa4fffc]);
+    assert_eq!(asm("sw $a0, -0x4 ( $sp )"), vec![0xafa4fffc]);
+    assert_eq!(asm("lwc1 $f0, 8($a0)"), vec![0xc4800008]);
+    assert_eq!(asm("lwc1 $f0, ($a0)"), vec![0xc4800000]);
+
+    let syms = HashMap::from_iter(vec![(0x80123456, "sym")]);
+    let insts = Mipsasm::new()
+        .symbols(syms)
+        .assemble("lw $a0, %lo(sym)($a1)\nswc1 $f2, %lo(sym)($a1)")
+        .unwrap();
+    assert_eq!(get_bytes(&insts), vec![0x8ca43456, 0xe4a23456]);
+}
+
+#[test]
+fn test_offset_base_invalid() {
+    assert!(matches!(
+        Mipsasm::new()
+            .assemble("lw $a0, 0x10")
+            .unwrap_err()
+            .as_slice(),
+        [ParserError::InvalidImmediate { .. }]
+    ));
+    assert!(matches!(
+        Mipsasm::new()
+            .assemble("lw $a0, 0x10($zz)")
+            .unwrap_err()
+            .as_slice(),
+        [ParserError::InvalidRegister { .. }]
+    ));
+}
+
+#[test]
+fn test_hi_lo_forward_label() {
+    assert_eq!(
+        asm("lui $a0, %hi(data)\naddiu $a0, $a0, %lo(data)\nlw $a1, %lo(data)($a0)\ndata:\nnop"),
+        vec![0x3c048000, 0x2484000c, 0x8c85000c, 0]
+    );
+    assert_eq!(
+        asm("data:\nnop\nlui $a0, %hi(data)\nori $a0, $a0, %lo(data)"),
+        vec![0, 0x3c048000, 0x34840000]
+    );
+    assert_eq!(
+        get_bytes(
+            &Mipsasm::new()
+                .base(0x80007ff8)
+                .assemble("lui $a0, %hi(data)\naddiu $a0, $a0, %lo(data)\nnop\nnop\ndata:")
+                .unwrap()
+        ),
+        vec![0x3c048001, 0x24848008, 0, 0]
+    );
+}
+
+#[test]
+fn test_forward_branch_label() {
+    assert_eq!(
+        asm("b foo\nnop\nbeqz $a0, foo\nnop\nfoo:\nnop"),
+        vec![0x10000003, 0, 0x10800001, 0, 0]
+    );
+}
+
+#[test]
+fn test_undefined_label() {
+    for inst in ["b foo", "lui $a0, %hi(foo)", "addiu $a0, $a0, %lo(foo)"] {
+        let errs = Mipsasm::new().assemble(inst).unwrap_err();
+        assert!(
+            matches!(errs.as_slice(), [ParserError::UndefinedLabel { .. }]),
+            "{}",
+            inst
+        );
+    }
+}
+
+#[test]
+fn test_unknown_operator() {
+    for (inst, operator) in [
+        ("addiu $a0, $a0, %xx(foo)", "%xx"),
+        ("lui $a0, %gp_rel(foo)", "%gp_rel"),
+        ("lui $a0, %hix(foo)", "%hix"),
+    ] {
+        let errs = Mipsasm::new().assemble(inst).unwrap_err();
+        assert!(
+            matches!(errs.as_slice(), [ParserError::UnknownOperator { operator: op, .. }] if op == operator),
+            "{}",
+            inst
+        );
+        assert!(errs[0].to_string().contains(operator));
+    }
+    // Too short to hold an operator
+    let errs = Mipsasm::new().assemble("addiu $a0, $a0, %x").unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::InvalidImmediate { .. }]
+    ));
+}
+
+#[test]
+fn test_hint_names() {
+    for (named, numeric) in [
+        ("cache Hit_Writeback_Inv_D, 0($a0)", "cache 0x15, 0($a0)"),
+        ("cache index_invalidate_i, 0x10($a0)", "cache 0, 0x10($a0)"),
+        ("pref store, 4($t0)", "pref 1, 4($t0)"),
+        ("pref PREPARE_FOR_STORE, 0($t0)", "pref 30, 0($t0)"),
+    ] {
+        assert_eq!(asm(named), asm(numeric), "{}", named);
+    }
+    // Names belong to a single instruction
+    assert!(Mipsasm::new().assemble("pref fill_i, 0($a0)").is_err());
+    assert!(Mipsasm::new().assemble("cache 32, 0($a0)").is_err());
+}
+
+#[test]
+fn test_mul_isa() {
+    let mul = |isa| {
+        get_bytes(
+            &Mipsasm::new()
+                .options(Options {
+                    isa,
+                    ..Default::default()
+                })
+                .assemble("mul $a0, $a1, $a2")
+                .unwrap(),
+        )
+    };
+    assert_eq!(mul(None), vec![0x00a60018, 0x00002012]);
+    assert_eq!(mul(Some(Isa::Mips3)), vec![0x00a60018, 0x00002012]);
+    assert_eq!(mul(Some(Isa::Mips32)), vec![0x70a62002]);
+    assert_eq!(mul(Some(Isa::Mips64r2)), vec![0x70a62002]);
+
+    assert_eq!(
+        Mipsasm::new().debug().disassemble(&[0x70a62002]),
+        vec!["mul $a0, $a1, $a2"]
+    );
+    // The shift amount field has to be zero
+    assert_eq!(
+        Mipsasm::new().debug().disassemble(&[0x70a62042]),
+        vec![".word 0x70a62042"]
+    );
+}
+
+#[test]
+fn test_ascii() {
+    assert_eq!(asm(".ascii \"abcd\""), vec![0x61626364]);
+    assert_eq!(asm(".ascii \"abcde\""), vec![0x61626364, 0x65000000]);
+    assert_eq!(asm(".asciiz \"abcd\""), vec![0x61626364, 0]);
+    assert_eq!(asm(".asciiz \"a, b\""), vec![0x612c2062, 0]);
+    assert_eq!(asm(".ascii \"ab\", \"cd\""), vec![0x61626364]);
+    assert_eq!(asm(".asciiz \"a\", \"b\""), vec![0x61006200]);
+    assert_eq!(asm(".ascii \"\\\"\\\\\\n\\x7f\""), vec![0x225c0a7f]);
+    assert_eq!(
+        asm(".ascii \"; // \" ; comment"),
+        vec![0x3b202f2f, 0x20000000]
+    );
+    assert_eq!(asm(".ascii \"\"\nnop"), vec![0]);
+}
+
+#[test]
+fn test_ascii_labels() {
+    assert_eq!(
+        asm(".asciiz \"hello\"\nfoo:\nb foo\nnop"),
+        vec![0x68656c6c, 0x6f000000, 0x1000ffff, 0]
+    );
+}
+
+#[test]
+fn test_ascii_invalid() {
+    for inst in [
+        ".ascii",
+        ".ascii abc",
+        ".ascii \"abc",
+        ".ascii \"a\"b\"",
+        ".ascii \"\\q\"",
+        ".ascii \"\\xZZ\"",
+    ] {
+        let errs = Mipsasm::new().assemble(inst).unwrap_err();
+        assert!(
+            matches!(errs.as_slice(), [ParserError::InvalidString { .. }]),
+            "{}",
+            inst
+        );
+    }
+}
+
+#[test]
+fn test_set_push_pop() {
+    assert_eq!(
+        asm("mul $a0, $a1, $a2
+            .set push
+            .set mips32
+            .set noreorder
+            mul $a0, $a1, $a2
+            .set pop
+            mul $a0, $a1, $a2"),
+        vec![0x00a60018, 0x00002012, 0x70a62002, 0x00a60018, 0x00002012]
+    );
+    assert_eq!(
+        asm(".set mips32
+            .set push
+            .set mips3
+            .set pop
+            mul $a0, $a1, $a2
+            .set mips0
+            mul $a0, $a1, $a2"),
+        vec![0x70a62002, 0x00a60018, 0x00002012]
+    );
+}
+
+#[test]
+fn test_set_invalid() {
+    for (input, msg) in [
+        (".set pop", "`.set pop` without a matching `.set push`"),
+        (
+            "nop\n.set push\nnop",
+            "`.set push` without a matching `.set pop`",
+        ),
+    ] {
+        let errs = Mipsasm::new().assemble(input).unwrap_err();
+        assert!(
+            matches!(errs.as_slice(), [ParserError::UnmatchedSet { .. }]),
+            "{}",
+            input
+        );
+        assert!(errs[0].to_string().contains(msg));
+    }
+
+    let errs = Mipsasm::new().assemble(".set mips99").unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::InvalidSetOption { .. }]
+    ));
+}
+
+#[test]
+fn test_ignored_set_options() {
+    assert_eq!(
+        asm(".set volatile
+            .set nomove
+            .set fp=64
+            .set NOMACRO
+            .set arch=mips32
+            mul $a0, $a1, $a2
+            .set novolatile"),
+        vec![0x70a62002]
+    );
+
+    let input = ".set frobnicate\nsyscall";
+    let errs = Mipsasm::new().assemble(input).unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::InvalidSetOption { .. }]
+    ));
+    let lenient = |warnings_as_errors| {
+        Mipsasm::new()
+            .options(Options {
+                allow_unknown_set: true,
+                warnings_as_errors,
+                ..Default::default()
+            })
+            .assemble(input)
+    };
+    assert_eq!(get_bytes(&lenient(false).unwrap()), vec![0x0000000c]);
+    let errs = lenient(true).unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::DeniedWarning { .. }]
+    ));
+    assert!(errs[0]
+        .to_string()
+        .contains("ignoring unknown `.set` option `frobnicate`"));
+}
+
+#[test]
+fn test_compressed_isa() {
+    let input = ".set mips16
+        foo:
+        addiu $a0, 1
+        jr $ra
+        .set nomips16
+        b foo
+        nop";
+    let errs = Mipsasm::new().assemble(input).unwrap_err();
+    // The MIPS16e code is skipped rather than assembled as 32-bit instructions
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::CompressedIsa { .. }]
+    ));
+    assert!(errs[0]
+        .to_string()
+        .contains("MIPS16e and microMIPS code is not supported"));
+
+    let errs = Mipsasm::new()
+        .options(Options {
+            allow_unknown_set: true,
+            ..Default::default()
+        })
+        .assemble(".set push\n.set micromips\n.set pop\nsyscall")
+        .unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::CompressedIsa { .. }]
+    ));
+}
+
+#[test]
+fn test_allow_unresolved() {
+    let input = "lui $a0, %hi(ext)
+        addiu $a0, $a0, %lo(ext)
+        jal ext
+        nop
+        bnez $a0, ext
+        nop";
+    let errs = Mipsasm::new().assemble(input).unwrap_err();
+    assert_eq!(errs.len(), 4);
+    assert!(errs
+        .iter()
+        .all(|e| matches!(e, ParserError::UndefinedLabel { .. })));
+
+    let insts = Mipsasm::new()
+        .base(0x80000000)
+        .options(Options {
+            allow_unresolved: true,
+            ..Default::default()
+        })
+        .assemble(input)
+        .unwrap();
+    assert_eq!(
+        get_bytes(&insts),
+        vec![0x3c040000, 0x24840000, 0x0c000000, 0, 0x14800000, 0]
+    );
+}
+
+#[test]
+fn test_org() {
+    assert_eq!(asm("nop\n.org 0x8000000c\nnop"), vec![0, 0, 0, 0]);
+    assert_eq!(
+        asm("nop\n.org 0x8000000c, 0xff\nnop"),
+        vec![0, 0xffffffff, 0xffffffff, 0]
+    );
+    assert_eq!(asm("nop\n.org 0x80000004\nnop"), vec![0, 0]);
+    assert_eq!(
+        asm(".org 0x80000008\nfoo:\nj foo\nnop"),
+        vec![0, 0, 0x08000002, 0]
+    );
+}
+
+#[test]
+fn test_org_invalid() {
+    let errs = Mipsasm::new()
+        .base(0x80000000)
+        .assemble("nop\nnop\n.org 0x80000004")
+        .unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::OrgBackwards {
+            addrs: (0x80000004, 0x80000008),
+            ..
+        }]
+    ));
+    assert!(errs[0]
+        .to_string()
+        .contains("0x80000004 is before the current address 0x80000008"));
+
+    for input in [
+        ".org 0x80000002",
+        ".org 0x80000004, 0x100",
+        ".org 0x80000004, 0, 0",
+    ] {
+        assert!(Mipsasm::new().base(0x80000000).assemble(input).is_err());
+    }
+}
+
+#[test]
+fn test_space() {
+    assert_eq!(asm("nop\n.space 8\nnop"), vec![0, 0, 0, 0]);
+    assert_eq!(
+        asm("nop\n.space 5, 0xff\nnop"),
+        vec![0, 0xffffffff, 0xffffffff, 0]
+    );
+    assert_eq!(asm("nop\n.space 0\nnop"), vec![0, 0]);
+    assert!(Mipsasm::new().base(0x80000000).assemble(".space").is_err());
+
+    // Padding is kept as a single item however long it is
+    let insts = Mipsasm::new()
+        .assemble("nop\n.space 0x100000\nnop")
+        .unwrap();
+    assert_eq!(insts.len(), 3);
+    assert_eq!(get_bytes(&insts).len(), 0x40002);
+}
+
+#[test]
+fn test_output_too_large() {
+    let errs = Mipsasm::new().assemble(".space 0x7ffffff0").unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::OutputTooLarge {
+            size: 0x7ffffff0,
+            max: 0x4000000,
+            ..
+        }]
+    ));
+    assert!(errs[0].to_string().contains("the limit is 0x4000000"));
+    // Sizes past the end of the address space are rejected before the limit is checked
+    assert!(matches!(
+        Mipsasm::new()
+            .assemble("nop\n.space 0xfffffffc")
+            .unwrap_err()
+            .as_slice(),
+        [ParserError::InvalidImmediate { .. }]
+    ));
+
+    let limited = Options {
+        max_output_size: 16,
+        ..Default::default()
+    };
+    assert!(Mipsasm::new()
+        .options(limited.clone())
+        .assemble(".space 16")
+        .is_ok());
+    assert!(matches!(
+        Mipsasm::new()
+            .options(limited)
+            .assemble(".space 16\nnop")
+            .unwrap_err()
+            .as_slice(),
+        [ParserError::OutputTooLarge { size: 20, .. }]
+    ));
+
+    assert!(matches!(
+        Mipsasm::new()
+            .base(0)
+            .assemble(".org 0x7ffffffc\nnop")
+            .unwrap_err()
+            .as_slice(),
+        [ParserError::OutputTooLarge {
+            size: 0x7ffffffc,
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn test_address_overflow() {
+    let errs = Mipsasm::new()
+        .base(0xfffffff4)
+        .assemble("nop\nnop\nfoo:\nnop\nj foo")
+        .unwrap_err();
+    assert!(matches!(
+        errs.as_slice(),
+        [ParserError::AddressOverflow { .. }]
+    ));
+    assert!(errs[0]
+        .to_string()
+        .contains("past the end of the address space"));
+    assert!(Mipsasm::new().base(0xfffffff0).assemble("nop\nnop").is_ok());
+
+    assert!(matches!(
+        Mipsasm::new()
+            .base(0xfffffff0)
+            .assemble("nop\n.org 0xfffffffc\nnop")
+            .unwrap_err()
+            .as_slice(),
+        [ParserError::AddressOverflow { .. }]
+    ));
+}
+
+#[test]
+fn test_fill_byte() {
+    let asm_filled = |input: &str| {
+        get_bytes(
+            &Mipsasm::new()
+                .base(0x80000000)
+                .options(Options {
+                    fill_byte: 0xff,
+                    ..Default::default()
+                })
+                .assemble(input)
+                .unwrap(),
+        )
+    };
+    assert_eq!(asm_filled("nop\n.align 3\nnop"), vec![0, 0xffffffff, 0]);
+    assert_eq!(asm_filled("nop\n.align 3, 0\nnop"), vec![0, 0, 0]);
+    assert_eq!(
+        asm_filled("nop\n.org 0x80000008\nnop"),
+        vec![0, 0xffffffff, 0]
+    );
+    assert_eq!(asm_filled(".space 4"), vec![0xffffffff]);
+    assert_eq!(asm_filled(".space 4, 0x12"), vec![0x12121212]);
+}
+
+#[test]
+fn test_cpload_cprestore() {
+    let source = ".cpload $25
+        addiu $sp, $sp, -32
+        .cprestore 16
+        jalr $25
+        nop";
+    let syms = HashMap::from_iter(vec![(0x80008000, "_gp")]);
+    let pic = |source: &str| {
+        get_bytes(
+            &Mipsasm::new()
+                .base(0x80000000)
+                .symbols(syms.clone())
+                .options(Options {
+                    pic: true,
+                    ..Default::default()
+                })
+                .assemble(source)
+                .unwrap(),
+        )
+    };
+    let expected = vec![
+        0x3c1c0001, 0x279c8000, 0x0399e021, 0x27bdffe0, 0xafbc0010, 0x0320f809, 0x00000000,
+    ];
+    assert_eq!(pic(source), expected);
+    assert_eq!(
+        get_bytes(
+            &Mipsasm::new()
+                .base(0x80000000)
+                .symbols(syms.clone())
+                .assemble(&format!(".abicalls\n{}", source))
+                .unwrap()
+        ),
+        expected
+    );
+
+    // `_gp_disp` is relative to the `lui` of the pair
+    assert_eq!(
+        pic("nop\n.cpload $t9\n_gp:"),
+        vec![0, 0x3c1c0000, 0x279c000c, 0x0399e021]
+    );
+
+    // Outside of PIC code both are ignored
+    assert_eq!(asm(source), vec![0x27bdffe0, 0x0320f809, 0x00000000]);
+    assert!(Mipsasm::new().assemble(".abicalls\n.cpload 4").is_err());
+}
+
+#[test]
+fn test_trap_code_invalid() {
+    for input in [
+        "teq $a0, $a1, 0x400",
+        "teq $a0, $a1, $a2",
+        "teq $a0",
+        "teq $a0, $a1, 1, 2",
+    ] {
+        assert!(Mipsasm::new().assemble(input).is_err());
+    }
+}
+
+#[test]
+fn test_set_overrides_isa_option() {
+    let insts = Mipsasm::new()
+        .options(Options {
+            isa: Some(Isa::Mips32),
+            ..Default::default()
+        })
+        .assemble(
+            "mul $a0, $a1, $a2
+            .set push
+            .set mips3
+            mul $a0, $a1, $a2
+            .set pop
+            mul $a0, $a1, $a2
+            .set mips1
+            .set mips0
+            mul $a0, $a1, $a2",
+        )
+        .unwrap();
+    assert_eq!(
+        get_bytes(&insts),
+        vec![0x70a62002, 0x00a60018, 0x00002012, 0x70a62002, 0x70a62002]
+    );
+}
+
+#[test]
+fn test_sync_wait_invalid() {
+    for input in ["sync 0x20", "sync 1, 2", "wait 0x80000"] {
+        assert!(Mipsasm::new().assemble(input).is_err(), "{}", input);
+    }
+    assert_eq!(
+        Mipsasm::new().debug().disassemble(&[0x0000f80f]),
+        vec![".word 0x0000f80f"]
+    );
+}
+
+#[test]
+fn test_cop_invalid() {
+    for input in ["cop2 0x2000000", "cop2", "cop0 1, 2"] {
+        assert!(Mipsasm::new().assemble(input).is_err(), "{}", input);
+    }
+    assert_eq!(
+        Mipsasm::new().debug().disassemble(&[0x48000000]),
+        vec![".word 0x48000000"]
+    );
+}
+
+#[test]
+fn test_trailing_h_hex() {
+    let insts = asm("addiu $a0, $a1, 0FFh\nori $a0, $a1, 100h\naddiu $a0, $a1, -10H");
+    assert_eq!(insts, vec![0x24a400ff, 0x34a40100, 0x24a4fff0]);
+
+    for (input, msg) in [
+        ("addiu $a0, $a1, FFh", "must start with a digit"),
+        ("addiu $a0, $a1, abc", "not a number or a defined label"),
+    ] {
+        let errors = Mipsasm::new().assemble(input).unwrap_err();
+        assert!(
+            matches!(errors.as_slice(), [ParserError::InvalidImmediate { .. }]),
+            "{}",
+            input
+        );
+        assert!(errors[0].to_string().contains(msg), "{}", errors[0]);
+    }
+}
+
+#[test]
+fn test_pc_relative_invalid() {
+    for input in [
+        "addiupc $a0, 0x80000002",
+        "lwpc $a0",
+        "lwpc $zz, 0x80000000",
+    ] {
+        assert!(Mipsasm::new().assemble(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_warnings_as_errors() {
+    let input = "foo:\nj foo\nb foo\nnop";
+    assert!(Mipsasm::new().assemble(input).is_ok());
+    let errors = Mipsasm::new()
+        .options(Options {
+            warnings_as_errors: true,
+            ..Default::default()
+        })
+        .assemble(input)
+        .unwrap_err();
+    assert!(matches!(
+        errors.as_slice(),
+        [ParserError::DeniedWarning { .. }]
+    ));
+    assert!(errors[0].to_string().contains("treated as an error"));
+}
+
+#[test]
+fn test_fill_delay_slots() {
+    let input = "foo:
+        beq $a0, $zero, bar
+        addiu $a0, $a0, 1
+        jr $ra
+        nop
+        bar:
+        jal foo
+        .set noreorder
+        b bar
+        addiu $a0, $a0, 1";
+    assert_eq!(asm(input).len(), 7);
+    let insts = Mipsasm::new()
+        .base(0x80000000)
+        .options(Options {
+            fill_delay_slots: true,
+            ..Default::default()
+        })
+        .assemble(input)
+        .unwrap();
+    assert_eq!(
+        get_bytes(&insts),
+        vec![
+            0x10800004, 0x00000000, 0x24840001, 0x03e00008, 0x00000000, 0x0c000000, 0x00000000,
+            0x1000fffd, 0x24840001
+        ]
+    );
+}
+
+#[test]
+fn test_malformed_input_is_an_error() {
+    for input in [
+        "foo:\n.word foo",
+        "foo:\n.align %hi(foo)",
+        "foo:\nsyscall foo",
+        "foo:\nli $a0, %lo(foo)",
+        "cache $a0, 0($a0)",
+        "cache @@x, 0($a0)",
+        "lw 0x10, 0($a0)",
+        "sll $a0, $a0, x",
+        "sll $a0, $a0, 0xz",
+        "addiu $a0, $a0, %x(foo)",
+        "addiu $a0, $a0, é(foo)",
+        "j é",
+        "foo:\n.cprestore %hi(foo)",
+        ":",
+        "jr $r1x",
+        "addu $a0, $a1, $tr3",
+        "jr $r99999999999",
+    ] {
+        let errs = Mipsasm::new().assemble(input).unwrap_err();
+        // Rendering the error must not panic either
+        assert!(!errs[0].to_string().is_empty(), "{input:?}");
+    }
+}
+
+#[test]
+fn test_shift_amount_range() {
+    for input in [
+        "sll $a0, $a1, -1",
+        "sll $a0, $a1, 32",
+        "srl $a0, $a1, 0x20",
+        "dsll32 $a0, $a1, 32",
+        "dsra $a0, $a1, -1",
+    ] {
+        assert!(
+            matches!(
+                Mipsasm::new().assemble(input).unwrap_err().as_slice(),
+                [ParserError::InvalidImmediate { .. }]
+            ),
+            "{input:?}"
+        );
+    }
+    assert_eq!(asm("sll $a0, $a1, 0"), vec![0x00052000]);
+    assert_eq!(asm("sra $a0, $a1, 31"), vec![0x000527c3]);
+}
+
+#[test]
+fn test_empty_label() {
+    for input in [":", "nop\n  :"] {
+        assert!(
+            matches!(
+                Mipsasm::new().assemble(input).unwrap_err().as_slice(),
+                [ParserError::InvalidLabel { .. }]
+            ),
+            "{input:?}"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use mipsasm::{Endian, Error, Mipsasm, Options, ParserError, ParserWarning};
+
+#[test]
+fn test_patch() {
+    let mut rom = vec![0xff; 12];
+    mipsasm::patch(
+        &mut rom,
+        0x80000004,
+        0x80000000,
+        "j 0x80000000",
+        Endian::Big,
+    )
+    .unwrap();
+    mipsasm::patch(&mut rom, 0x80000008, 0x80000000, "jr $ra", Endian::Little).unwrap();
+    assert_eq!(
+        rom,
+        vec![0xff, 0xff, 0xff, 0xff, 0x08, 0x00, 0x00, 0x00, 0x08, 0x00, 0xe0, 0x03]
+    );
+}
+
+#[test]
+fn test_patch_errors() {
+    let mut rom = vec![0; 8];
+    for addr in [0x7ffffffc, 0x80000006, 0x80000008, 0xffffffff] {
+        assert!(matches!(
+            mipsasm::patch(&mut rom, addr, 0x80000000, "nop", Endian::Big),
+            Err(Error::OutOfBounds { .. })
+        ));
+    }
+    assert!(matches!(
+        mipsasm::patch(
+            &mut rom,
+            0x80000000,
+            0x80000000,
+            "li $t0, 0x12345678",
+            Endian::Big
+        ),
+        Err(Error::NotOneWord(2))
+    ));
+    assert!(matches!(
+        mipsasm::patch(&mut rom, 0x80000000, 0x80000000, "foo $t0", Endian::Big),
+        Err(Error::Parse(_))
+    ));
+    assert_eq!(rom, vec![0; 8]);
+}
+
+#[test]
+fn test_assemble_one() {
+    let mipsasm = Mipsasm::new();
+    assert_eq!(
+        mipsasm.assemble_one("add $a0, $a1, $a2").unwrap(),
+        0x00a62020
+    );
+    assert!(matches!(
+        mipsasm.assemble_one(""),
+        Err(Error::NotOneWord(0))
+    ));
+}
+
+#[test]
+fn test_error_sources() {
+    use std::error::Error as _;
+
+    let err = Error::from(Mipsasm::new().assemble("foo $a0").unwrap_err());
+    let source = err.source().unwrap();
+    assert!(source.downcast_ref::<ParserError>().is_some());
+
+    let err = Mipsasm::new()
+        .options(Options {
+            warnings_as_errors: true,
+            ..Default::default()
+        })
+        .assemble_one("j 0x80000002")
+        .unwrap_err();
+    let warning = err.source().and_then(|source| source.source()).unwrap();
+    assert!(warning.downcast_ref::<ParserWarning>().is_some());
+
+    let err = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+    assert!(err
+        .source()
+        .unwrap()
+        .downcast_ref::<std::io::Error>()
+        .is_some());
+    assert!(Error::NotOneWord(2).source().is_none());
+}
@@ -0,0 +1,523 @@
+use mipsasm::{Isa, Lint, Mipsasm, Options, RelocKind, Severity};
+use std::collections::HashMap;
+
+#[test]
+fn test_relocation_offsets() {
+    let syms = HashMap::from_iter(vec![(0x80001000, "func"), (0x80123456, "data")]);
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .symbols(syms)
+        .assemble_program(
+            "li $t0, 0x12345678
+            jal func
+            nop
+            lui $a0, %hi(data)
+            addiu $a0, $a0, %lo(data)
+            j 0x80000000
+            nop",
+        )
+        .unwrap();
+    let words = program.words();
+
+    let relocs = program.relocations();
+    assert_eq!(relocs.len(), 3);
+    assert_eq!(
+        relocs
+            .iter()
+            .map(|r| (r.offset, r.kind, r.symbol.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (8, RelocKind::Jump26, "func"),
+            (16, RelocKind::Hi16, "data"),
+            (20, RelocKind::Lo16, "data"),
+        ]
+    );
+    assert_eq!(words[relocs[0].offset as usize / 4], 0x0c000400);
+    assert_eq!(words[relocs[1].offset as usize / 4], 0x3c048012);
+    assert_eq!(words[relocs[2].offset as usize / 4], 0x24843456);
+}
+
+#[test]
+fn test_relocation_labels() {
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program(
+            "start:
+            nop
+            j start
+            nop",
+        )
+        .unwrap();
+    let relocs = program.relocations();
+    assert_eq!(relocs.len(), 1);
+    assert_eq!(relocs[0].offset, 4);
+    assert_eq!(relocs[0].symbol, "start");
+    assert_eq!(program.words()[1], 0x08000000);
+}
+
+#[test]
+fn test_labels() {
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program(
+            "main:
+            li $t0, 0x12345678
+            @@loop:
+            b @@loop
+            nop
+            helper:
+            jr $ra
+            nop
+            end:",
+        )
+        .unwrap();
+    assert_eq!(
+        program.labels().collect::<Vec<_>>(),
+        vec![
+            ("main", 0x80000000),
+            ("helper", 0x80000010),
+            ("end", 0x80000018)
+        ]
+    );
+    assert_eq!(program.label("helper"), Some(0x80000010));
+    assert_eq!(program.label("@@loop"), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_labels_json() {
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program("main:\nnop\nhelper:\njr $ra")
+        .unwrap();
+    assert_eq!(
+        program.labels_json(),
+        "{\n  \"main\": \"0x80000000\",\n  \"helper\": \"0x80000004\"\n}"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_to_json() {
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program("main:\nb main\n.word 0x12345678\n.space 4, 0xff")
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&program.to_json()).unwrap();
+    assert_eq!(json["labels"]["main"], "0x80000000");
+    let words = json["words"].as_array().unwrap();
+    assert_eq!(
+        words
+            .iter()
+            .map(|w| (
+                w["address"].as_str().unwrap(),
+                w["word"].as_str().unwrap(),
+                w["line"].as_u64().unwrap(),
+                w["text"].as_str().unwrap(),
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                "0x80000000",
+                "0x1000ffff",
+                2,
+                "beq        $zero, $zero, main"
+            ),
+            ("0x80000004", "0x12345678", 3, ".word      0x12345678"),
+            ("0x80000008", "0xffffffff", 4, ".word      0xffffffff"),
+        ]
+    );
+}
+
+#[test]
+fn test_display_round_trip() {
+    let source = "main:
+        li $t0, 0x12345678
+        @@loop:
+        addiu $t0, $t0, -1
+        bnez $t0, @@loop
+        nop
+        jal helper
+        nop
+        j 0x80000000
+        nop
+        helper:
+        jr $ra
+        nop
+        data:
+        .asciiz \"hi\"
+        .word 0xdeadbeef
+        end:";
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program(source)
+        .unwrap();
+    let listing = program.to_string();
+    assert_eq!(
+        listing.lines().collect::<Vec<_>>(),
+        vec![
+            "main:",
+            "    lui        $t0, 0x1234               // 0x80000000",
+            "    ori        $t0, $t0, 0x5678          // 0x80000004",
+            "    addiu      $t0, $t0, -0x1            // 0x80000008",
+            "    bne        $t0, $zero, 0x80000008    // 0x8000000c",
+            "    sll        $zero, $zero, 0x0         // 0x80000010",
+            "    jal        helper                    // 0x80000014",
+            "    sll        $zero, $zero, 0x0         // 0x80000018",
+            "    j          main                      // 0x8000001c",
+            "    sll        $zero, $zero, 0x0         // 0x80000020",
+            "helper:",
+            "    jr         $ra                       // 0x80000024",
+            "    sll        $zero, $zero, 0x0         // 0x80000028",
+            "data:",
+            "    .word      0x68690000                // 0x8000002c",
+            "    .word      0xdeadbeef                // 0x80000030",
+            "end:",
+        ]
+    );
+
+    let reassembled = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program(&listing)
+        .unwrap();
+    assert_eq!(reassembled.words(), program.words());
+    assert_eq!(
+        reassembled.labels().collect::<Vec<_>>(),
+        program.labels().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_validate() {
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program(
+            "addiu $zero, $a0, 1
+            j foo
+            b foo
+            nop
+            add.d $f0, $f1, $f2
+            .set mips1
+            ll $a0, 0($a1)
+            .set mips0
+            ll $a0, 0($a1)
+            foo:
+            nop
+            sll $zero, $zero, 0",
+        )
+        .unwrap();
+    assert_eq!(
+        program
+            .validate()
+            .iter()
+            .map(|d| (d.severity, d.line))
+            .collect::<Vec<_>>(),
+        vec![
+            (Severity::Warning, 1),
+            (Severity::Warning, 3),
+            (Severity::Warning, 5),
+            (Severity::Error, 7),
+        ]
+    );
+    assert!(program.validate()[3].to_string().contains("requires mips2"));
+
+    let program = Mipsasm::new()
+        .options(Options {
+            isa: Some(mipsasm::Isa::Mips32),
+            ..Default::default()
+        })
+        .assemble_program("mul $a0, $a1, $a2\ndadd $a0, $a1, $a2")
+        .unwrap();
+    assert_eq!(program.validate().len(), 1);
+    assert_eq!(program.validate()[0].line, 2);
+}
+
+#[test]
+fn test_validate_branch_range() {
+    let program = Mipsasm::new()
+        .assemble_program("b far\nnop\n.space 0x20000\nfar:\nb far")
+        .unwrap();
+    let diagnostics = program.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(diagnostics[0].line, 1);
+}
+
+#[test]
+fn test_map() {
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program(
+            "main:
+            start:
+            jal helper
+            nop
+            .align 4, 0xff
+            helper:
+            jr $ra
+            .ascii \"abcde\"",
+        )
+        .unwrap();
+    assert_eq!(
+        program.map().lines().collect::<Vec<_>>(),
+        vec![
+            "address,word,line,label",
+            "0x80000000,0x0c000004,3,main;start",
+            "0x80000004,0x00000000,4,",
+            "0x80000008,0xffffffff,5,",
+            "0x8000000c,0xffffffff,5,",
+            "0x80000010,0x03e00008,7,helper",
+            "0x80000014,0x61626364,8,",
+            "0x80000018,0x65000000,8,",
+        ]
+    );
+}
+
+#[test]
+fn test_pc_relative() {
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .assemble_program(
+            ".set mips32r2
+            lwpc $a0, data
+            .set mips32r6
+            addiupc $a0, data
+            data:
+            .word 0",
+        )
+        .unwrap();
+    let diagnostics = program.validate();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        (diagnostics[0].severity, diagnostics[0].line),
+        (Severity::Error, 2)
+    );
+    assert!(diagnostics[0].to_string().contains("requires mips32r6"));
+
+    let listing = program.to_string();
+    assert!(listing.contains("lwpc       $a0, data"), "{}", listing);
+    assert!(listing.contains("addiupc    $a0, data"), "{}", listing);
+    assert_eq!(
+        Mipsasm::new()
+            .base(0x80000000)
+            .assemble_program(&listing)
+            .unwrap()
+            .words(),
+        program.words()
+    );
+}
+
+#[test]
+fn test_validate_hazards() {
+    let program = Mipsasm::new()
+        .base(0x80000000)
+        .options(Options {
+            isa: Some(Isa::Mips1),
+            ..Default::default()
+        })
+        .assemble_program(
+            "lw $t0, 0($a0)
+            addu $t1, $t0, $t0
+            b target
+            lw $t2, 0($a0)
+            nop
+            target:
+            sw $t2, 0($a1)
+            mflo $t3
+            mult $t3, $t3
+            .set push
+            .set nohazards
+            lw $t0, 0($a0)
+            addu $t1, $t0, $t0
+            .set pop
+            .set mips2
+            lw $t0, 0($a0)
+            addu $t1, $t0, $t0",
+        )
+        .unwrap();
+    let diagnostics = program.validate();
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line))
+            .collect::<Vec<_>>(),
+        vec![
+            (Severity::Warning, 2),
+            (Severity::Warning, 7),
+            (Severity::Warning, 9),
+        ]
+    );
+    assert!(diagnostics[1].message.contains("branch target"));
+    assert!(diagnostics[2].message.contains("HI and LO"));
+}
+
+#[test]
+fn test_load_delays_default_isa() {
+    // The default target interlocks on loads, so only MIPS I code is checked
+    let source = "lw $t0, 0($a0)
+        addu $t1, $t0, $t0";
+    let validate = |options: Options| {
+        Mipsasm::new()
+            .options(options)
+            .assemble_program(source)
+            .unwrap()
+            .validate()
+    };
+    assert!(validate(Options::default()).is_empty());
+    assert!(validate(Options {
+        warnings_as_errors: true,
+        ..Default::default()
+    })
+    .is_empty());
+    assert_eq!(
+        validate(Options {
+            isa: Some(Isa::Mips1),
+            ..Default::default()
+        })
+        .len(),
+        1
+    );
+}
+
+#[test]
+fn test_lint_levels() {
+    let source = "addiu $zero, $a0, 1
+        lw $t0, 0($a0)
+        addu $t1, $t0, $t0
+        j foo
+        foo:
+        nop";
+    let validate = |options: Options| {
+        Mipsasm::new()
+            .options(Options {
+                isa: Some(Isa::Mips1),
+                ..options
+            })
+            .assemble_program(source)
+            .unwrap()
+            .validate()
+            .iter()
+            .map(|d| (d.severity, d.line))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        validate(Options::default()),
+        vec![(Severity::Warning, 1), (Severity::Warning, 3)]
+    );
+    assert_eq!(
+        validate(Options {
+            lints: HashMap::from([
+                (Lint::ZeroWrites, None),
+                (Lint::LoadDelays, Some(Severity::Error)),
+            ]),
+            ..Default::default()
+        }),
+        vec![(Severity::Error, 3)]
+    );
+    assert_eq!(
+        validate(Options {
+            warnings_as_errors: true,
+            ..Default::default()
+        }),
+        vec![(Severity::Error, 1), (Severity::Error, 3)]
+    );
+}
+
+#[test]
+fn test_paired_single_isa() {
+    let program = Mipsasm::new()
+        .assemble_program(
+            ".set mips4
+            add.ps $f0, $f2, $f4
+            cvt.ps.s $f0, $f2, $f4
+            c.lt.ps $f2, $f4
+            .set mips64
+            add.ps $f0, $f2, $f4
+            cvt.ps.s $f0, $f2, $f4
+            .set mips64r6
+            add.ps $f0, $f2, $f4",
+        )
+        .unwrap();
+    let diagnostics = program.validate();
+    assert_eq!(
+        diagnostics.iter().map(|d| d.line).collect::<Vec<_>>(),
+        vec![2, 3, 4, 9]
+    );
+    assert!(diagnostics[0].to_string().contains("requires mips5"));
+}
+
+#[test]
+fn test_system_control_isa() {
+    let source = "eret\ncache 0x14, 0($a0)";
+    for isa in [Isa::Mips3, Isa::Mips32, Isa::Mips32r2, Isa::Mips64] {
+        let program = Mipsasm::new()
+            .options(Options {
+                isa: Some(isa),
+                ..Default::default()
+            })
+            .assemble_program(source)
+            .unwrap();
+        assert!(program.validate().is_empty(), "{}", isa);
+    }
+    let diagnostics = Mipsasm::new()
+        .assemble_program(&format!(".set mips2\n{}", source))
+        .unwrap()
+        .validate();
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics[0]
+        .to_string()
+        .contains("requires mips3 or mips32, but the ISA is mips2"));
+}
+
+#[test]
+fn test_encoded_size() {
+    for (input, size) in [
+        ("nop", 4),
+        ("li $t0, 0x1234", 4),
+        ("li $t0, 0x12345678", 8),
+        ("bge $a0, $a1, foo\nnop\nfoo:", 12),
+        ("nop\n.align 4\nnop", 20),
+        ("nop\n.align 3\n.asciiz \"hello\"", 16),
+    ] {
+        let mipsasm = Mipsasm::new();
+        assert_eq!(mipsasm.encoded_size(input).unwrap(), size, "{}", input);
+        let program = mipsasm.assemble_program(input).unwrap();
+        assert_eq!(program.encoded_size(), size, "{}", input);
+        assert_eq!(program.words().len() * 4, size, "{}", input);
+    }
+
+    // Labels aren't resolved, so undefined ones don't matter
+    assert_eq!(Mipsasm::new().encoded_size("j foo\nnop").unwrap(), 8);
+    assert!(Mipsasm::new().encoded_size("addiu $a0, $a1").is_err());
+}
+
+#[test]
+fn test_stats() {
+    let program = Mipsasm::new()
+        .assemble_program(
+            "main:
+            addiu $sp, $sp, -8
+            bge $a0, $a1, done
+            div $a0, $a1, $a2
+            move $v0, $a0
+            done:
+            jr $ra
+            nop
+            .align 6
+            .asciiz \"hi\"
+            .space 4",
+        )
+        .unwrap();
+    let stats = program.stats();
+    // `bge` is two words, `div` eight with its checks, and `move` and `nop` one each
+    assert_eq!(
+        (
+            stats.instructions,
+            stats.expansions,
+            stats.data,
+            stats.padding
+        ),
+        (2, 12, 1, 3)
+    );
+    assert_eq!(stats.total(), program.words().len());
+}